@@ -1,8 +1,18 @@
 use string_builder::Builder;
-use std::time::Duration;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 
+use snakers::config::{GameConfig, WallPolicy};
+use snakers::controller::{bot_by_name, Controller, BOT_NAMES};
+use snakers::event::GameEvent;
+use snakers::food::FoodKind;
 use snakers::game::*;
-use snakers::inputtype::InputType;
+use snakers::inputtype::{Controls, InputType};
+use snakers::level::Level;
+use snakers::powerup::PowerUp;
+use snakers::speed::SpeedCurve;
+use snakers::replay::{Recorder, Replay};
+use snakers::topology::Shape;
 
 use crossterm::event::{read, poll, Event, KeyCode, KeyEvent};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
@@ -10,72 +20,429 @@ use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
 const WIDTH : u32 = 48;
 const HEIGHT : u32 = 18;
 
+// hex cells take two characters each, so fewer of them fit across
+const HEX_WIDTH : u32 = 24;
+const HEX_HEIGHT : u32 = 18;
+
+// how far back backspace goes
+const REWIND_TIME : Duration = Duration::from_secs(3);
+
+struct Options {
+  shape: Shape,
+  wall_policy: WallPolicy,
+  level: Option<Level>,
+  speed: Option<SpeedCurve>,
+  controller: String,
+  controls: Controls,
+  log_level: log::LevelFilter,
+  log_file: Option<String>,
+  record: Option<String>,
+  replay: Option<Replay>,
+}
+
+fn usage(error: &str) -> ! {
+  eprintln!("{}", error);
+  eprintln!("usage: snakers-cli [--shape square|hex] [--walls die|wrap|bounce] [--level FILE] [--speed CURVE]");
+  eprintln!("                   [--controller keyboard|{}] [--controls absolute|relative]", BOT_NAMES.join("|"));
+  eprintln!("                   [--log-level off|error|warn|info|debug|trace] [--log-file FILE]");
+  eprintln!("                   [--record FILE | --replay FILE]");
+  std::process::exit(2);
+}
+
+fn parse_args() -> Options {
+  let mut options = Options {
+    shape: Shape::Square,
+    wall_policy: WallPolicy::Die,
+    level: None,
+    speed: None,
+    controller: "keyboard".to_string(),
+    controls: Controls::Absolute,
+    log_level: log::LevelFilter::Warn,
+    log_file: None,
+    record: None,
+    replay: None,
+  };
+
+  let mut args = std::env::args().skip(1);
+  while let Some(arg) = args.next() {
+    let mut value = || args.next().unwrap_or_else(|| usage(&format!("{} needs a value", arg)));
+    match arg.as_str() {
+      "--shape" => options.shape = value().parse().unwrap_or_else(|e: String| usage(&e)),
+      "--walls" => options.wall_policy = value().parse().unwrap_or_else(|e: String| usage(&e)),
+      "--level" => options.level = Some(read_level(&value())),
+      "--speed" => options.speed = Some(value().parse().unwrap_or_else(|e: String| usage(&e))),
+      "--controller" => options.controller = value(),
+      "--controls" => options.controls = value().parse().unwrap_or_else(|e: String| usage(&e)),
+      "--log-level" => options.log_level = value().parse().unwrap_or_else(|_| usage("unknown log level")),
+      "--log-file" => options.log_file = Some(value()),
+      "--record" => options.record = Some(value()),
+      "--replay" => options.replay = Some(read_replay(&value())),
+      _ => usage(&format!("unknown option '{}'", arg)),
+    }
+  }
+
+  if options.record.is_some() && options.replay.is_some() {
+    usage("--record and --replay can't be used together");
+  }
+
+  if options.controller != "keyboard" && options.replay.is_some() {
+    usage("--controller and --replay can't be used together");
+  }
+
+  options
+}
+
+fn make_controller(name: &str, turn_queue_depth: usize, controls: Controls) -> Box<dyn Controller> {
+  match name {
+    "keyboard" => Box::new(Keyboard::new(turn_queue_depth, controls)),
+    _ => bot_by_name(name).unwrap_or_else(|| usage(&format!("unknown controller '{}'", name))),
+  }
+}
+
+fn read_level(path: &str) -> Level {
+  let text = std::fs::read_to_string(path)
+    .unwrap_or_else(|e| usage(&format!("can't read level {}: {}", path, e)));
+  Level::parse(&text).unwrap_or_else(|e| usage(&format!("bad level {}: {}", path, e)))
+}
+
+// to the file if there is one, otherwise to stderr so it stays out of the way of the board
+fn init_logging(options : &Options) {
+  let config = simplelog::Config::default();
+  let result = match &options.log_file {
+    Some(path) => {
+      let file = std::fs::File::create(path)
+        .unwrap_or_else(|e| usage(&format!("can't create log file {}: {}", path, e)));
+      simplelog::WriteLogger::init(options.log_level, config, file)
+    },
+    None => simplelog::TermLogger::init(
+      options.log_level, config, simplelog::TerminalMode::Stderr, simplelog::ColorChoice::Auto),
+  };
+  if let Err(e) = result {
+    eprintln!("can't start logging: {}", e);
+  }
+}
+
+fn read_replay(path: &str) -> Replay {
+  let text = std::fs::read_to_string(path)
+    .unwrap_or_else(|e| usage(&format!("can't read replay {}: {}", path, e)));
+  Replay::parse(&text).unwrap_or_else(|e| usage(&format!("bad replay {}: {}", path, e)))
+}
+
 fn main() {
-  let mut game = GameState::new(WIDTH, HEIGHT);
+  let options = parse_args();
+
+  init_logging(&options);
+
+  let (width, height) = match options.shape {
+    Shape::Square => (WIDTH, HEIGHT),
+    Shape::Hex => (HEX_WIDTH, HEX_HEIGHT),
+  };
+
+  let mut builder = GameConfig::builder()
+    .size(width, height)
+    .shape(options.shape)
+    .wall_policy(options.wall_policy);
+
+  if let Some(level) = &options.level {
+    builder = level.apply(builder);
+  }
+
+  if let Some(speed) = options.speed {
+    builder = builder.speed(speed);
+  }
+
+  let config = builder.build().unwrap_or_else(|e| usage(&e.to_string()));
+
+  // a replay brings its own seed and config
+  let mut game = match &options.replay {
+    Some(replay) => replay.new_game(),
+    None => GameState::new(config),
+  }.unwrap_or_else(|e| usage(&e.to_string()));
+
+  let mut recorder = options.record.as_ref().map(|_| Recorder::new(&game));
+
+  let mut controller = make_controller(&options.controller, game.get_config().get_turn_queue_depth(), options.controls);
 
   loop {
-    draw(game.get_world());
-    let input = get_input();
-    match game.handle_input(input) {
-      StateTransition::Stop => break,
-      _ => (),
+    let outcome = loop {
+      draw(&game);
+      draw_hud(&game);
+      draw_events(&game);
+
+      // q quits and backspace rewinds whoever is playing, other keys are up to the controller
+      let mut quit = false;
+      let mut rewind = false;
+      for input in get_inputs(game.tick_interval()) {
+        match input {
+          InputType::Quit => quit = true,
+          InputType::Rewind => rewind = true,
+          _ => controller.key_pressed(input),
+        }
+      }
+
+      // a replay has to play out as it was recorded
+      if rewind && !quit && options.replay.is_none() {
+        rewind_game(&mut game, &mut recorder);
+        continue;
+      }
+
+      // a replay brings its own inputs
+      let input = match (quit, &options.replay) {
+        (true, _) => InputType::Quit,
+        (false, Some(_)) => InputType::Nothing,
+        (false, None) => controller.next_input(&game),
+      };
+
+      if let Some(recorder) = &mut recorder {
+        recorder.record(&game, input);
+      }
+      if let StateTransition::Stop(outcome) = game.handle_input(input) {
+        break outcome;
+      }
+
+      let transition = match &options.replay {
+        Some(replay) => replay.step(&mut game),
+        None => game.update_state(),
+      };
+      if let StateTransition::Stop(outcome) = transition {
+        break outcome;
+      }
+    };
+
+    draw(&game);
+    draw_game_over(&outcome, frame_width(&game));
+
+    // a death can be taken back, but not a quit or the end of a replay
+    if outcome.cause == GameOverCause::Quit || options.replay.is_some() || !wait_for_rewind() {
+      break;
+    }
+    rewind_game(&mut game, &mut recorder);
+  }
+
+  if let (Some(path), Some(recorder)) = (&options.record, recorder) {
+    if let Err(e) = std::fs::write(path, recorder.finish(&game).to_string()) {
+      eprintln!("can't write replay {}: {}", path, e);
+      std::process::exit(1);
     }
-    match game.update_state() {
-      StateTransition::Stop => break,
-      _ => (),
+  }
+
+  if let Some(replay) = &options.replay {
+    match replay.verify(&game) {
+      Ok(()) => println!("replay verified: final state {:016x}", game.state_hash()),
+      Err(e) => {
+        eprintln!("replay did not match the recording: {}", e);
+        std::process::exit(1);
+      },
     }
   }
 }
 
-fn get_input() -> InputType {
-  let mut input = InputType::Nothing;
+// back REWIND_TIME's worth of ticks at the speed the game is going now, and the recording with it
+fn rewind_game(game : &mut GameState, recorder : &mut Option<Recorder>) {
+  let ticks = REWIND_TIME.as_nanos() / game.tick_interval().as_nanos().max(1);
+  game.rewind((ticks as usize).max(1));
+  if let Some(recorder) = recorder {
+    recorder.rewind(game);
+  }
+}
+
+// after a game over: true for backspace, false for any other key
+fn wait_for_rewind() -> bool {
+  println!(" backspace rewinds, any other key quits");
   enable_raw_mode().unwrap();
-  // this pol does not work
-  let poll_result = poll(Duration::from_millis(750));
-  if let Ok(_) = poll_result {
-    input = match read().unwrap() {
-      Event::Key(KeyEvent {
-        code: KeyCode::Char('q'), ..
-      }) => InputType::Quit,
-      Event::Key(KeyEvent { code: KeyCode::Up, ..  }) => InputType::Up,
-      Event::Key(KeyEvent { code: KeyCode::Right, ..  }) => InputType::Right,
-      Event::Key(KeyEvent { code: KeyCode::Down, ..  }) => InputType::Down,
-      Event::Key(KeyEvent { code: KeyCode::Left, ..  }) => InputType::Left,
-      Event::Key(_) => {
-        // println!("{:?}", event);
-        InputType::Nothing
-      },
-      _ => InputType::Nothing,
-    };
+  let rewind = loop {
+    if let Event::Key(KeyEvent { code, .. }) = read().unwrap() {
+      break code == KeyCode::Backspace;
+    }
+  };
+  disable_raw_mode().unwrap();
+  rewind
+}
+
+/*
+ * the person at the terminal. keys wait here and go to the game one a tick, the same way queued
+ * turns do, and like the turn queue it only holds so many.
+ * */
+struct Keyboard {
+  pending: VecDeque<InputType>,
+  depth: usize,
+  controls: Controls,
+}
+
+impl Keyboard {
+  fn new(depth : usize, controls : Controls) -> Self {
+    Keyboard { pending: VecDeque::new(), depth, controls }
+  }
+}
+
+impl Controller for Keyboard {
+  fn next_input(&mut self, _game : &GameState) -> InputType {
+    self.pending.pop_front().unwrap_or(InputType::Nothing)
+  }
+
+  fn key_pressed(&mut self, input : InputType) {
+    let input = self.controls.map(input);
+    if input != InputType::Nothing && self.pending.len() < self.depth {
+      self.pending.push_back(input);
+    }
+  }
+}
+
+// the keys pressed until the interval is up, or until quit
+fn get_inputs(interval : Duration) -> Vec<InputType> {
+  let deadline = Instant::now() + interval;
+  let mut inputs = Vec::new();
+  enable_raw_mode().unwrap();
+  loop {
+    let now = Instant::now();
+    if now >= deadline {
+      break;
+    }
+    // only read when there is something to read, otherwise read blocks until a key is pressed
+    let poll_result = poll(deadline - now);
+    if let Ok(true) = poll_result {
+      let input = match read().unwrap() {
+        Event::Key(KeyEvent {
+          code: KeyCode::Char('q'), ..
+        }) => InputType::Quit,
+        Event::Key(KeyEvent { code: KeyCode::Backspace, ..  }) => InputType::Rewind,
+        Event::Key(KeyEvent { code: KeyCode::Up, ..  }) => InputType::Up,
+        Event::Key(KeyEvent { code: KeyCode::Right, ..  }) => InputType::Right,
+        Event::Key(KeyEvent { code: KeyCode::Down, ..  }) => InputType::Down,
+        Event::Key(KeyEvent { code: KeyCode::Left, ..  }) => InputType::Left,
+        // the hex diagonals, on the keys around s that point their way
+        Event::Key(KeyEvent { code: KeyCode::Char('w'), ..  }) => InputType::UpLeft,
+        Event::Key(KeyEvent { code: KeyCode::Char('e'), ..  }) => InputType::UpRight,
+        Event::Key(KeyEvent { code: KeyCode::Char('z'), ..  }) => InputType::DownLeft,
+        Event::Key(KeyEvent { code: KeyCode::Char('x'), ..  }) => InputType::DownRight,
+        Event::Key(_) => {
+          // println!("{:?}", event);
+          InputType::Nothing
+        },
+        _ => InputType::Nothing,
+      };
+      match input {
+        InputType::Nothing => (),
+        InputType::Quit => {
+          inputs.push(input);
+          break;
+        },
+        _ => inputs.push(input),
+      }
+    }
   }
   disable_raw_mode().unwrap();
-  input
+  inputs
 }
 
-fn draw(world : &GridType) {
+fn item_char(item : &ItemType) -> &'static str {
+  match item {
+    ItemType::Nothing  => " ",
+    ItemType::Food(FoodKind::Regular) => "O",
+    ItemType::Food(FoodKind::Big)     => "@",
+    ItemType::Food(FoodKind::Shrink)  => "-",
+    ItemType::Food(FoodKind::Golden)  => "$",
+    ItemType::SnakeBit => "S",
+    ItemType::SnakeHead => "%",
+    ItemType::SnakeTail => "*",
+    ItemType::PowerUp(PowerUp::Ghost)       => "g",
+    ItemType::PowerUp(PowerUp::SlowMotion)  => "s",
+    ItemType::PowerUp(PowerUp::DoubleScore) => "x",
+    ItemType::PowerUp(PowerUp::Magnet)      => "m",
+    ItemType::Wall     => "#",
+  }
+}
 
-  println!("+{0}+", "-".repeat(WIDTH as usize));
+// how many characters wide the inside of the board is drawn
+fn frame_width(game : &GameState) -> u32 {
+  match game.get_board().get_shape() {
+    Shape::Square => game.get_width(),
+    Shape::Hex => 2 * game.get_width() + 1,
+  }
+}
+
+/*
+ * hex cells are a character and a space each, with every odd row pushed over by one, so each cell
+ * sits between the two above it the way the board connects them:
+ *
+ *   |O S S    |
+ *   | * %     |
+ * */
+fn draw(game : &GameState) {
+
+  let width = frame_width(game) as usize;
+  let shape = game.get_board().get_shape();
 
-  for y in 0..HEIGHT {
+  println!("+{0}+", "-".repeat(width));
 
-    let mut builder = Builder::new((WIDTH + 2) as usize);
+  for (y, row) in game.get_world().iter().enumerate() {
+
+    let mut builder = Builder::new(width + 2);
 
     builder.append("|");
 
-    for x in 0..WIDTH {
-      match &world[y as usize][x as usize] {
-        ItemType::Nothing  => builder.append(" "),
-        ItemType::Food     => builder.append("O"),
-        ItemType::SnakeBit => builder.append("S"),
-        ItemType::SnakeHead => builder.append("%"),
-        ItemType::SnakeTail => builder.append("*"),
+    let shifted = y % 2 == 1;
+    if shape == Shape::Hex && shifted {
+      builder.append(" ");
+    }
+
+    for item in row.iter() {
+      builder.append(item_char(item));
+      if shape == Shape::Hex {
+        builder.append(" ");
       }
     }
 
+    if shape == Shape::Hex && !shifted {
+      builder.append(" ");
+    }
+
     builder.append("|");
 
     println!("{0}", builder.string().unwrap());
   }
 
-  println!("+{0}+", "-".repeat(WIDTH as usize));
+  println!("+{0}+", "-".repeat(width));
+}
+
+fn draw_hud(game : &GameState) {
+  let stats = game.get_stats();
+  let mut hud = format!(
+    "score {}  length {}  food {}  turns {}  ticks {}",
+    stats.get_score(),
+    game.get_snake().get_body().len(),
+    stats.get_food_eaten(),
+    stats.get_turns(),
+    stats.get_ticks(),
+  );
+  for effect in game.get_snake().get_effects() {
+    hud.push_str(&format!("  {} {}", effect.kind, effect.ticks_left));
+  }
+  println!(" {0:<1$} ", hud, frame_width(game) as usize);
+}
+
+// a line of messages for what happened on the last tick
+fn draw_events(game : &GameState) {
+  let messages: Vec<String> = game.get_events().iter().filter_map(|event| match event {
+    // these happen all the time, so they would only be noise
+    GameEvent::FoodSpawned(..) | GameEvent::DirectionChanged(_) => None,
+    event => Some(event.to_string()),
+  }).collect();
+  println!(" {0:<1$} ", messages.join(", "), frame_width(game) as usize);
+}
+
+fn draw_game_over(outcome : &GameOutcome, width : u32) {
+  let lines = [
+    if outcome.cause.is_win() { "YOU WIN" } else { "GAME OVER" }.to_string(),
+    format!("the snake {} at {}", outcome.cause, outcome.at),
+    format!("tick {}", outcome.tick),
+    format!("length {}", outcome.length),
+    format!("score {}", outcome.score),
+  ];
+
+  for line in lines.iter() {
+    println!("|{0:^1$}|", line, width as usize);
+  }
+
+  println!("+{0}+", "-".repeat(width as usize));
 }
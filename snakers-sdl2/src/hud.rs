@@ -0,0 +1,106 @@
+/*
+ * there is no font shipped with the game, so text is drawn from a tiny built-in 3x5 pixel font.
+ * each glyph is five rows of three bits, most significant bit on the left. lower case letters are
+ * drawn as upper case and anything unknown is drawn as '?'.
+ * */
+
+const GLYPH_WIDTH: i32 = 3;
+const GLYPH_HEIGHT: i32 = 5;
+
+// blank column between glyphs
+const GLYPH_SPACING: i32 = 1;
+
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ';' => [0b000, 0b010, 0b000, 0b010, 0b100],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+pub fn text_width(text: &str, scale: u32) -> u32 {
+    let count = text.chars().count() as i32;
+    if count == 0 {
+        return 0;
+    }
+    (((count * (GLYPH_WIDTH + GLYPH_SPACING)) - GLYPH_SPACING) * scale as i32) as u32
+}
+
+pub fn text_height(scale: u32) -> u32 {
+    GLYPH_HEIGHT as u32 * scale
+}
+
+// draw text with its top left corner at (x, y), each font pixel a scale x scale square
+pub fn draw_text(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+                 x: i32,
+                 y: i32,
+                 scale: u32,
+                 color: sdl2::pixels::Color,
+                 text: &str)
+{
+    canvas.set_draw_color(color);
+
+    let step = scale as i32;
+
+    for (i, c) in text.chars().enumerate() {
+        let left = x + (i as i32 * (GLYPH_WIDTH + GLYPH_SPACING) * step);
+
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
+                    let _ = canvas.fill_rect(sdl2::rect::Rect::new(
+                        left + (col * step),
+                        y + (row as i32 * step),
+                        scale,
+                        scale,
+                    ));
+                }
+            }
+        }
+    }
+}
@@ -2,13 +2,25 @@
 // provides pie and filled_pie for sdl2::render::Canvas
 use sdl2::gfx::primitives::DrawRenderer;
 
+mod hud;
 mod textures;
 
 use textures::SnakeTextureManager;
 
+use std::collections::VecDeque;
+
+use snakers::config::{GameConfig, WallPolicy};
+use snakers::controller::{bot_by_name, Controller, BOT_NAMES};
 use snakers::game::*;
 use snakers::direction::Direction;
-use snakers::inputtype::InputType;
+use snakers::event::GameEvent;
+use snakers::food::FoodKind;
+use snakers::inputtype::{Controls, InputType};
+use snakers::level::Level;
+use snakers::powerup::PowerUp;
+use snakers::speed::SpeedCurve;
+use snakers::replay::{Recorder, Replay};
+use snakers::topology::Shape;
 
 const WIDTH_PIXELS: u32 = 1200;
 const HEIGHT_PIXELS: u32 = 750;
@@ -25,12 +37,26 @@ const FRAME_DURATION: std::time::Duration = std::time::Duration::from_nanos((1_0
 
 const RATE_LIMITED: bool = true;
 
-const TICKS_PER_SECOND: f64 = 0.5;
-const TICK_DURATION: std::time::Duration = std::time::Duration::from_nanos((1_000_000_000.0 / TICKS_PER_SECOND) as u64);
+// how far back backspace goes
+const REWIND_TIME: std::time::Duration = std::time::Duration::from_secs(3);
+
 
 const FOOD_COLOR: sdl2::pixels::Color = sdl2::pixels::Color::RGB(200, 200, 20);
+const BIG_FOOD_COLOR: sdl2::pixels::Color = sdl2::pixels::Color::RGB(230, 120, 20);
+const SHRINK_FOOD_COLOR: sdl2::pixels::Color = sdl2::pixels::Color::RGB(150, 60, 200);
+const GOLDEN_FOOD_COLOR: sdl2::pixels::Color = sdl2::pixels::Color::RGB(255, 215, 0);
+const WALL_COLOR: sdl2::pixels::Color = sdl2::pixels::Color::RGB(110, 90, 80);
 const RED: sdl2::pixels::Color = sdl2::pixels::Color::RGB(255, 0, 0);
 const BLUE: sdl2::pixels::Color = sdl2::pixels::Color::RGB(0, 0, 255);
+const HUD_COLOR: sdl2::pixels::Color = sdl2::pixels::Color::RGB(40, 40, 40);
+const HUD_SCALE: u32 = 4;
+const HUD_MARGIN_PX: i32 = 8;
+const GAME_OVER_TEXT_COLOR: sdl2::pixels::Color = sdl2::pixels::Color::RGB(255, 255, 255);
+const GAME_OVER_SHADE: sdl2::pixels::Color = sdl2::pixels::Color::RGBA(0, 0, 0, 160);
+const POWER_UP_COLOR: sdl2::pixels::Color = sdl2::pixels::Color::RGB(40, 170, 220);
+const POWER_UP_TEXT_COLOR: sdl2::pixels::Color = sdl2::pixels::Color::RGB(255, 255, 255);
+const FLASH_COLOR: (u8, u8, u8) = (230, 160, 20);
+const FLASH_FRAMES: u64 = 15;
 
 /*
  * lifetime notes
@@ -53,17 +79,188 @@ struct SDLContext<'a> {
     // frame_duration_ewma: u64,
     frame_percent: f64,
 
+    // where food was just eaten, in grid coordinates, and the frame it happened on
+    flashes: Vec<(snakers::coord::Coord, u64)>,
+
     stm: SnakeTextureManager<'a>,
 }
 
+struct Options {
+    wall_policy: WallPolicy,
+    level: Option<Level>,
+    speed: Option<SpeedCurve>,
+    controller: String,
+    controls: Controls,
+    log_level: log::LevelFilter,
+    log_file: Option<String>,
+    record: Option<String>,
+    replay: Option<Replay>,
+}
+
+fn usage(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!("usage: snakers-sdl2 [--walls die|wrap|bounce] [--level FILE] [--speed CURVE]");
+    eprintln!("                    [--controller keyboard|{}] [--controls absolute|relative]", BOT_NAMES.join("|"));
+    eprintln!("                    [--log-level off|error|warn|info|debug|trace] [--log-file FILE]");
+    eprintln!("                    [--record FILE | --replay FILE]");
+    std::process::exit(2);
+}
+
+fn parse_args() -> Options {
+    let mut options = Options {
+        wall_policy: WallPolicy::Die,
+        level: None,
+        speed: None,
+        controller: "keyboard".to_string(),
+        controls: Controls::Absolute,
+        log_level: log::LevelFilter::Warn,
+        log_file: None,
+        record: None,
+        replay: None,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage(&format!("{} needs a value", arg)));
+        match arg.as_str() {
+            "--walls" => options.wall_policy = value().parse().unwrap_or_else(|e: String| usage(&e)),
+            "--level" => options.level = Some(read_level(&value())),
+            "--speed" => options.speed = Some(value().parse().unwrap_or_else(|e: String| usage(&e))),
+            "--controller" => options.controller = value(),
+            "--controls" => options.controls = value().parse().unwrap_or_else(|e: String| usage(&e)),
+            "--log-level" => options.log_level = value().parse().unwrap_or_else(|_| usage("unknown log level")),
+            "--log-file" => options.log_file = Some(value()),
+            "--record" => options.record = Some(value()),
+            "--replay" => options.replay = Some(read_replay(&value())),
+            _ => usage(&format!("unknown option '{}'", arg)),
+        }
+    }
+
+    if options.record.is_some() && options.replay.is_some() {
+        usage("--record and --replay can't be used together");
+    }
+
+    if options.controller != "keyboard" && options.replay.is_some() {
+        usage("--controller and --replay can't be used together");
+    }
+
+    options
+}
+
+fn make_controller(name: &str, turn_queue_depth: usize, controls: Controls) -> Box<dyn Controller> {
+    match name {
+        "keyboard" => Box::new(Keyboard::new(turn_queue_depth, controls)),
+        _ => bot_by_name(name).unwrap_or_else(|| usage(&format!("unknown controller '{}'", name))),
+    }
+}
+
+// back REWIND_TIME's worth of ticks at the speed the game is going now, and the recording with it
+fn rewind_game(game: &mut GameState, recorder: &mut Option<Recorder>) {
+    let ticks = REWIND_TIME.as_nanos() / game.tick_interval().as_nanos().max(1);
+    game.rewind((ticks as usize).max(1));
+    if let Some(recorder) = recorder {
+        recorder.rewind(game);
+    }
+}
+
+/*
+ * the person at the window. keys wait here and go to the game one a tick, the same way queued
+ * turns do, and like the turn queue it only holds so many.
+ * */
+struct Keyboard {
+    pending: VecDeque<InputType>,
+    depth: usize,
+    controls: Controls,
+}
+
+impl Keyboard {
+    fn new(depth: usize, controls: Controls) -> Self {
+        Keyboard { pending: VecDeque::new(), depth, controls }
+    }
+}
+
+impl Controller for Keyboard {
+    fn next_input(&mut self, _game: &GameState) -> InputType {
+        self.pending.pop_front().unwrap_or(InputType::Nothing)
+    }
+
+    fn key_pressed(&mut self, input: InputType) {
+        let input = self.controls.map(input);
+        if input != InputType::Nothing && self.pending.len() < self.depth {
+            self.pending.push_back(input);
+        }
+    }
+}
+
+fn read_level(path: &str) -> Level {
+    let text = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| usage(&format!("can't read level {}: {}", path, e)));
+    Level::parse(&text).unwrap_or_else(|e| usage(&format!("bad level {}: {}", path, e)))
+}
+
+// to the file if there is one, otherwise to stderr
+fn init_logging(options: &Options) {
+    let config = simplelog::Config::default();
+    let result = match &options.log_file {
+        Some(path) => {
+            let file = std::fs::File::create(path)
+                .unwrap_or_else(|e| usage(&format!("can't create log file {}: {}", path, e)));
+            simplelog::WriteLogger::init(options.log_level, config, file)
+        }
+        None => simplelog::TermLogger::init(options.log_level,
+                                            config,
+                                            simplelog::TerminalMode::Stderr,
+                                            simplelog::ColorChoice::Auto),
+    };
+    if let Err(e) = result {
+        eprintln!("can't start logging: {}", e);
+    }
+}
+
+fn read_replay(path: &str) -> Replay {
+    let text = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| usage(&format!("can't read replay {}: {}", path, e)));
+    Replay::parse(&text).unwrap_or_else(|e| usage(&format!("bad replay {}: {}", path, e)))
+}
+
 fn main() {
 
+    let options = parse_args();
+
+    init_logging(&options);
+
+    let mut builder = GameConfig::builder()
+        .size(WIDTH, HEIGHT)
+        .wall_policy(options.wall_policy);
+
+    if let Some(level) = &options.level {
+        builder = level.apply(builder);
+    }
+
+    if let Some(speed) = options.speed {
+        builder = builder.speed(speed);
+    }
+
+    // a replay brings its own seed and config
+    let config = match &options.replay {
+        Some(replay) => replay.get_config().clone(),
+        None => builder.build().unwrap_or_else(|e| usage(&e.to_string())),
+    };
+
+    // the textures are all square tiles
+    if config.get_shape() != Shape::Square {
+        usage(&format!("can't draw a {} board, only square ones", config.get_shape()));
+    }
+
     let sdl_context = sdl2::init().unwrap();
 
     let video_subsystem = sdl_context.video().unwrap();
 
+    // one tile per cell, so a level can make the window bigger or smaller than the default
     let mut window = video_subsystem
-        .window("snake.rs - SDL2 Driver", WIDTH_PIXELS, HEIGHT_PIXELS)
+        .window("snake.rs - SDL2 Driver",
+                config.get_width() * GAME_TO_PIXEL,
+                config.get_height() * GAME_TO_PIXEL)
         .position(0, 0)
         .build()
         .unwrap();
@@ -95,6 +292,7 @@ fn main() {
         frame_counter: 0,
         tick_counter: 0,
         frame_percent: 0.0,
+        flashes: Vec::new(),
         stm: stm,
     };
 
@@ -102,49 +300,112 @@ fn main() {
     ctx.start_time = ctx.last_frame_time;
     ctx.timer_freq = sdl2::TimerSubsystem::performance_frequency(&ctx.timer);
 
-    let mut game = GameState::new(WIDTH, HEIGHT);
+    let mut game = match &options.replay {
+        Some(replay) => replay.new_game(),
+        None => GameState::new(config),
+    }.unwrap_or_else(|e| usage(&e.to_string()));
+
+    let mut recorder = options.record.as_ref().map(|_| Recorder::new(&game));
+
+    let mut controller = make_controller(&options.controller, game.get_config().get_turn_queue_depth(), options.controls);
 
     ctx.last_tick_time = sdl2::TimerSubsystem::performance_counter(&ctx.timer);
 
     let mut last_tick_frame_number = ctx.frame_counter;
 
     loop {
-        ctx.draw(&game);
+        let outcome = loop {
+            ctx.draw(&game);
+
+            // q quits and backspace rewinds whoever is playing, other keys are up to the controller
+            match ctx.get_input() {
+                InputType::Quit => {
+                    if let Some(recorder) = &mut recorder {
+                        recorder.record(&game, InputType::Quit);
+                    }
+                    if let StateTransition::Stop(outcome) = game.handle_input(InputType::Quit) {
+                        break outcome;
+                    }
+                }
+                // a replay has to play out as it was recorded
+                InputType::Rewind if options.replay.is_none() => {
+                    rewind_game(&mut game, &mut recorder);
+                    ctx.flashes.clear();
+                }
+                InputType::Nothing | InputType::Rewind => (),
+                input => controller.key_pressed(input),
+            }
 
-        let input = ctx.get_input();
+            let cur_time = sdl2::TimerSubsystem::performance_counter(&ctx.timer);
 
-        match game.handle_input(input) {
-            StateTransition::Stop => break,
-            _ => (),
-        }
+            // the game says how long a tick should take, it speeds up as the game goes on
+            let tick_duration = game.tick_interval();
 
-        let cur_time = sdl2::TimerSubsystem::performance_counter(&ctx.timer);
+            ctx.frame_percent = ((cur_time - ctx.last_tick_time) as f64) / std::time::Duration::as_nanos(&tick_duration) as f64;
 
-        ctx.frame_percent = ((cur_time - ctx.last_tick_time) as f64) / std::time::Duration::as_nanos(&TICK_DURATION) as f64;
+            if ctx.frame_percent >= 1.0 {
 
-        if ctx.frame_percent >= 1.0 {
+                ctx.frame_percent = ctx.frame_percent - 1.0;
 
-            ctx.frame_percent = ctx.frame_percent - 1.0;
+                log::debug!(
+                    "frames: {}; Tick FPS: {:.02}; Avg FPS: {:.02}",
+                    ctx.frame_counter,
+                    1e9 * (((ctx.frame_counter - last_tick_frame_number) as f64)
+                        / ((cur_time - ctx.last_tick_time) as f64)),
+                    1e9 * ((ctx.frame_counter as f64) / ((cur_time - ctx.start_time) as f64)),
+                );
 
-            println!(
-                "frames: {}; Tick FPS: {:.02}; Avg FPS: {:.02}",
-                ctx.frame_counter,
-                1e9 * (((ctx.frame_counter - last_tick_frame_number) as f64)
-                    / ((cur_time - ctx.last_tick_time) as f64)),
-                1e9 * ((ctx.frame_counter as f64) / ((cur_time - ctx.start_time) as f64)),
-            );
+                // a replay brings its own inputs
+                if options.replay.is_none() {
+                    let input = controller.next_input(&game);
+                    if let Some(recorder) = &mut recorder {
+                        recorder.record(&game, input);
+                    }
+                    game.handle_input(input);
+                }
+
+                let transition = match &options.replay {
+                    Some(replay) => replay.step(&mut game),
+                    None => game.update_state(),
+                };
+
+                ctx.react(&game);
+
+                match transition {
+                    StateTransition::Stop(outcome) => break outcome,
+                    _ => (),
+                }
 
-            match game.update_state() {
-                StateTransition::Stop => break,
-                _ => (),
+                last_tick_frame_number = ctx.frame_counter;
+                ctx.tick_counter += 1;
+                ctx.last_tick_time = cur_time;
             }
 
-            last_tick_frame_number = ctx.frame_counter;
-            ctx.tick_counter += 1;
-            ctx.last_tick_time = cur_time;
+            ctx.frame_counter += 1;
+        };
+
+        println!("{}", outcome);
+
+        if let Some(replay) = &options.replay {
+            match replay.verify(&game) {
+                Ok(()) => println!("replay verified: final state {:016x}", game.state_hash()),
+                Err(e) => eprintln!("replay did not match the recording: {}", e),
+            }
         }
 
-        ctx.frame_counter += 1;
+        // a death can be taken back, but not a quit or the end of a replay
+        let rewindable = outcome.cause != GameOverCause::Quit && options.replay.is_none();
+        if !ctx.draw_game_over(&game, &outcome, rewindable) {
+            break;
+        }
+        rewind_game(&mut game, &mut recorder);
+        ctx.last_tick_time = sdl2::TimerSubsystem::performance_counter(&ctx.timer);
+    }
+
+    if let (Some(path), Some(recorder)) = (&options.record, recorder) {
+        if let Err(e) = std::fs::write(path, recorder.finish(&game).to_string()) {
+            eprintln!("can't write replay {}: {}", path, e);
+        }
     }
 }
 
@@ -170,10 +431,10 @@ fn rotate_rect(center: &(i32, i32), rect: &sdl2::rect::Rect, direction: &Directi
 
     // find the rotated top left
     let pts: ((i32, i32), (i32, i32)) = match direction {
-        Direction::Up    => (rotated[0], rotated[2]),
-        Direction::Down  => (rotated[2], rotated[0]),
-        Direction::Left  => (rotated[1], rotated[3]),
-        Direction::Right => (rotated[3], rotated[1]),
+        Direction::Up    | Direction::UpRight   => (rotated[0], rotated[2]),
+        Direction::Down  | Direction::DownLeft  => (rotated[2], rotated[0]),
+        Direction::Left  | Direction::UpLeft    => (rotated[1], rotated[3]),
+        Direction::Right | Direction::DownRight => (rotated[3], rotated[1]),
     };
 
     let out = sdl2::rect::Rect::new (pts.0.0,
@@ -187,18 +448,158 @@ fn rotate_rect(center: &(i32, i32), rect: &sdl2::rect::Rect, direction: &Directi
 }
 
 impl SDLContext<'_> {
-    fn draw_food(&mut self, at: &(usize, usize)) {
-        self.canvas.set_draw_color(FOOD_COLOR);
+    fn draw_food(&mut self, at: &(usize, usize), kind: FoodKind) {
+        // big food fills its cell, shrink pills are small, golden food is round
+        let (color, margin) = match kind {
+            FoodKind::Regular => (FOOD_COLOR, CELL_MARGIN_PX),
+            FoodKind::Big => (BIG_FOOD_COLOR, 0),
+            FoodKind::Shrink => (SHRINK_FOOD_COLOR, CELL_MARGIN_PX * 4),
+            FoodKind::Golden => (GOLDEN_FOOD_COLOR, CELL_MARGIN_PX),
+        };
+
+        if kind == FoodKind::Golden {
+            let _ = self.canvas.filled_circle(
+                (at.0 as u32 * GAME_TO_PIXEL + GAME_TO_PIXEL / 2) as i16,
+                (at.1 as u32 * GAME_TO_PIXEL + GAME_TO_PIXEL / 2) as i16,
+                (GAME_TO_PIXEL / 2 - margin) as i16,
+                color,
+            );
+            return;
+        }
+
+        self.canvas.set_draw_color(color);
         let _ = self.canvas.fill_rect(sdl2::rect::Rect::new(
-            ((at.0 as u32 * GAME_TO_PIXEL) + CELL_MARGIN_PX) as i32,
-            ((at.1 as u32 * GAME_TO_PIXEL) + CELL_MARGIN_PX) as i32,
-            GAME_TO_PIXEL - (CELL_MARGIN_PX * 2),
-            GAME_TO_PIXEL - (CELL_MARGIN_PX * 2),
+            ((at.0 as u32 * GAME_TO_PIXEL) + margin) as i32,
+            ((at.1 as u32 * GAME_TO_PIXEL) + margin) as i32,
+            GAME_TO_PIXEL - (margin * 2),
+            GAME_TO_PIXEL - (margin * 2),
+        ));
+    }
+
+    // a disc with the power-up's initial on it
+    fn draw_power_up(&mut self, at: &(usize, usize), kind: PowerUp) {
+        let center = (
+            (at.0 as u32 * GAME_TO_PIXEL + GAME_TO_PIXEL / 2) as i32,
+            (at.1 as u32 * GAME_TO_PIXEL + GAME_TO_PIXEL / 2) as i32,
+        );
+        let _ = self.canvas.filled_circle(center.0 as i16,
+                                          center.1 as i16,
+                                          (GAME_TO_PIXEL / 2 - CELL_MARGIN_PX) as i16,
+                                          POWER_UP_COLOR);
+
+        let letter = match kind {
+            PowerUp::Ghost => "G",
+            PowerUp::SlowMotion => "S",
+            PowerUp::DoubleScore => "2",
+            PowerUp::Magnet => "M",
+        };
+        hud::draw_text(&mut self.canvas,
+                       center.0 - hud::text_width(letter, HUD_SCALE) as i32 / 2,
+                       center.1 - hud::text_height(HUD_SCALE) as i32 / 2,
+                       HUD_SCALE,
+                       POWER_UP_TEXT_COLOR,
+                       letter);
+    }
+
+    fn draw_wall(&mut self, at: &(usize, usize)) {
+        self.canvas.set_draw_color(WALL_COLOR);
+        let _ = self.canvas.fill_rect(sdl2::rect::Rect::new(
+            (at.0 as u32 * GAME_TO_PIXEL) as i32,
+            (at.1 as u32 * GAME_TO_PIXEL) as i32,
+            GAME_TO_PIXEL,
+            GAME_TO_PIXEL,
         ));
     }
 
     fn draw(&mut self, game: &GameState) {
 
+        self.render_world(game);
+
+        self.canvas.present();
+
+        if RATE_LIMITED {
+            let cur_time: u64 = sdl2::TimerSubsystem::performance_counter(&self.timer);
+            let frame_elapsed: u64 = cur_time - self.last_frame_time;
+            let time_to_next_frame =
+                FRAME_DURATION - std::time::Duration::from_secs(frame_elapsed / self.timer_freq);
+
+            if time_to_next_frame > std::time::Duration::from_nanos(0) {
+                std::thread::sleep(time_to_next_frame);
+            }
+
+            self.last_frame_time = cur_time;
+        }
+    }
+
+    /*
+     * the final board stays up dimmed under the outcome until a key is pressed or the window
+     * closes. returns true when the key was backspace and the game can be rewound.
+     * */
+    fn draw_game_over(&mut self, game: &GameState, outcome: &GameOutcome, rewindable: bool) -> bool {
+        let _ = self.canvas.window_mut().set_title(&format!("snake.rs - {}", outcome));
+
+        // the frame counter stops here, so these would never fade
+        self.flashes.clear();
+
+        loop {
+            self.render_world(game);
+
+            self.canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+            self.canvas.set_draw_color(GAME_OVER_SHADE);
+            let _ = self.canvas.fill_rect(None);
+            self.canvas.set_blend_mode(sdl2::render::BlendMode::None);
+
+            // mark where it ended
+            let at = game.game_to_grid(&outcome.at);
+            let _ = self.canvas.filled_circle(
+                (at.x * GAME_TO_PIXEL as i32 + GAME_TO_PIXEL as i32 / 2) as i16,
+                (at.y * GAME_TO_PIXEL as i32 + GAME_TO_PIXEL as i32 / 2) as i16,
+                (GAME_TO_PIXEL / 3) as i16,
+                RED,
+            );
+
+            let mut lines = vec![
+                if outcome.cause.is_win() { "YOU WIN" } else { "GAME OVER" }.to_string(),
+                format!("THE SNAKE {}", outcome.cause),
+                format!("SCORE {}  LENGTH {}  TICK {}", outcome.score, outcome.length, outcome.tick),
+            ];
+            if rewindable {
+                lines.push("BACKSPACE TO REWIND".to_string());
+            }
+
+            let (width_px, height_px) = self.canvas.output_size().unwrap_or((WIDTH_PIXELS, HEIGHT_PIXELS));
+            let line_height = (hud::text_height(HUD_SCALE * 2) * 2) as i32;
+            let top = (height_px as i32 - (line_height * lines.len() as i32)) / 2;
+
+            for (i, line) in lines.iter().enumerate() {
+                let width = hud::text_width(line, HUD_SCALE * 2) as i32;
+                hud::draw_text(&mut self.canvas,
+                               (width_px as i32 - width) / 2,
+                               top + (i as i32 * line_height),
+                               HUD_SCALE * 2,
+                               GAME_OVER_TEXT_COLOR,
+                               line);
+            }
+
+            self.canvas.present();
+
+            for event in self.event_pump.poll_iter() {
+                match event {
+                    sdl2::event::Event::KeyDown {
+                        keycode: Some(sdl2::keyboard::Keycode::Backspace),
+                        ..
+                    } if rewindable => return true,
+                    sdl2::event::Event::Quit { .. } | sdl2::event::Event::KeyDown { .. } => return false,
+                    _ => (),
+                }
+            }
+
+            std::thread::sleep(FRAME_DURATION);
+        }
+    }
+
+    fn render_world(&mut self, game: &GameState) {
+
         // update background
         self.canvas.set_draw_color(sdl2::pixels::Color::RGB(255, 255, 255));
         self.canvas.clear();
@@ -213,32 +614,75 @@ impl SDLContext<'_> {
         */
 
         // render the grid
-        for y in 0..HEIGHT as usize {
-            for x in 0..WIDTH as usize {
+        for y in 0..game.get_height() as usize {
+            for x in 0..game.get_width() as usize {
                 match &game.get_world()[y][x] {
                     ItemType::Nothing => (),
-                    ItemType::Food => self.draw_food(&(x, y)),
+                    ItemType::Food(kind) => self.draw_food(&(x, y), *kind),
+                    ItemType::PowerUp(kind) => self.draw_power_up(&(x, y), *kind),
+                    ItemType::Wall => self.draw_wall(&(x, y)),
                     // ItemType::SnakeBit | ItemType::SnakeHead | ItemType::SnakeTail => self.draw_snake(x, y),
                     _ => (),
                 }
             }
         }
 
-        self.stm.draw_snake(self.frame_percent, &game, &mut self.canvas);
+        self.stm.draw_snake(self.frame_percent, game, &mut self.canvas);
 
-        self.canvas.present();
+        self.draw_flashes();
 
-        if RATE_LIMITED {
-            let cur_time: u64 = sdl2::TimerSubsystem::performance_counter(&self.timer);
-            let frame_elapsed: u64 = cur_time - self.last_frame_time;
-            let time_to_next_frame =
-                FRAME_DURATION - std::time::Duration::from_secs(frame_elapsed / self.timer_freq);
+        self.draw_hud(game);
+    }
 
-            if time_to_next_frame > std::time::Duration::from_nanos(0) {
-                std::thread::sleep(time_to_next_frame);
+    // start effects for whatever happened on the tick just played
+    fn react(&mut self, game: &GameState) {
+        for event in game.get_events() {
+            if let GameEvent::FoodEaten(at, _) = event {
+                self.flashes.push((game.game_to_grid(at), self.frame_counter));
             }
+        }
+    }
 
-            self.last_frame_time = cur_time;
+    // a ring that grows and fades out around where food was eaten
+    fn draw_flashes(&mut self) {
+        let frame = self.frame_counter;
+        self.flashes.retain(|(_, start)| frame - start < FLASH_FRAMES);
+
+        self.canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+        for (at, start) in self.flashes.iter() {
+            let age = (frame - start) as f64 / FLASH_FRAMES as f64;
+            let _ = self.canvas.circle(
+                (at.x * GAME_TO_PIXEL as i32 + GAME_TO_PIXEL as i32 / 2) as i16,
+                (at.y * GAME_TO_PIXEL as i32 + GAME_TO_PIXEL as i32 / 2) as i16,
+                (GAME_TO_PIXEL as f64 * (0.5 + age)) as i16,
+                sdl2::pixels::Color::RGBA(FLASH_COLOR.0, FLASH_COLOR.1, FLASH_COLOR.2, (255.0 * (1.0 - age)) as u8),
+            );
+        }
+        self.canvas.set_blend_mode(sdl2::render::BlendMode::None);
+    }
+
+    fn draw_hud(&mut self, game: &GameState) {
+        let stats = game.get_stats();
+        let hud = format!(
+            "SCORE {}  LENGTH {}  FOOD {}  TICKS {}",
+            stats.get_score(),
+            game.get_snake().get_body().len(),
+            stats.get_food_eaten(),
+            stats.get_ticks(),
+        );
+        hud::draw_text(&mut self.canvas, HUD_MARGIN_PX, HUD_MARGIN_PX, HUD_SCALE, HUD_COLOR, &hud);
+
+        // running power-ups go on a line of their own underneath
+        let effects: Vec<String> = game.get_snake().get_effects().iter()
+            .map(|effect| format!("{} {}", effect.kind, effect.ticks_left))
+            .collect();
+        if !effects.is_empty() {
+            hud::draw_text(&mut self.canvas,
+                           HUD_MARGIN_PX,
+                           HUD_MARGIN_PX * 2 + hud::text_height(HUD_SCALE) as i32,
+                           HUD_SCALE,
+                           POWER_UP_COLOR,
+                           &effects.join("  "));
         }
     }
 
@@ -264,6 +708,12 @@ impl SDLContext<'_> {
                 } => {
                     return InputType::Quit;
                 }
+                sdl2::event::Event::KeyDown {
+                    keycode: Some(sdl2::keyboard::Keycode::Backspace),
+                    ..
+                } => {
+                    return InputType::Rewind;
+                }
                 sdl2::event::Event::KeyDown {
                     keycode: Some(sdl2::keyboard::Keycode::Up),
                     ..
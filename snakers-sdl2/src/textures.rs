@@ -1,6 +1,7 @@
 
 use sdl2::gfx::primitives::DrawRenderer;
 
+use snakers::config::WallPolicy;
 use snakers::direction::Direction;
 use snakers::game::CoordWithDirection;
 use snakers::game::GameState;
@@ -58,12 +59,60 @@ impl<'a> SnakeTextureManager<'a> {
         };
     }
 
+    /*
+     * on a wrapping board a piece of snake hanging off one edge of the board has to show up
+     * coming in from the opposite edge rather than disappearing (or being drawn across the whole
+     * screen on its way there), so give back the rect plus a copy for each edge it crosses.
+     * */
+    fn wrapped_rects(&self, game: &GameState, rect: sdl2::rect::Rect) -> Vec<sdl2::rect::Rect> {
+        let mut rects = vec![rect];
+
+        if game.get_wall_policy() != WallPolicy::Wrap {
+            return rects;
+        }
+
+        let board_w = (game.get_width() * self.tile_dimension) as i32;
+        let board_h = (game.get_height() * self.tile_dimension) as i32;
+
+        let dx = if rect.left() < 0 {
+            board_w
+        } else if rect.right() > board_w {
+            -board_w
+        } else {
+            0
+        };
+
+        let dy = if rect.top() < 0 {
+            board_h
+        } else if rect.bottom() > board_h {
+            -board_h
+        } else {
+            0
+        };
+
+        if dx != 0 {
+            rects.push(sdl2::rect::Rect::new(rect.x() + dx, rect.y(), rect.width(), rect.height()));
+        }
+        if dy != 0 {
+            rects.push(sdl2::rect::Rect::new(rect.x(), rect.y() + dy, rect.width(), rect.height()));
+        }
+        if dx != 0 && dy != 0 {
+            rects.push(sdl2::rect::Rect::new(rect.x() + dx, rect.y() + dy, rect.width(), rect.height()));
+        }
+
+        rects
+    }
+
     fn get_direction_angle(direction: &Direction) -> f64 {
         match direction {
             Direction::Up => 270.0,
             Direction::Right => 0.0,
             Direction::Down => 90.0,
             Direction::Left => 180.0,
+            Direction::UpRight => 300.0,
+            Direction::DownRight => 60.0,
+            Direction::DownLeft => 120.0,
+            Direction::UpLeft => 240.0,
         }
     }
 
@@ -384,7 +433,7 @@ impl<'a> SnakeTextureManager<'a> {
             _   => incoming_angle_deg + ((target_angle_deg - incoming_angle_deg) * frame_percent),
         };
 
-        println!("In={:?}:{}; Out={:?}:{}; fwd={:?}; avg={:?}", incoming_direction, incoming_angle_deg, target_direction, target_angle_deg, forward_angle_deg, average);
+        log::trace!("In={:?}:{}; Out={:?}:{}; fwd={:?}; avg={:?}", incoming_direction, incoming_angle_deg, target_direction, target_angle_deg, forward_angle_deg, average);
 
         // find target root point in grid
         let (tx, ty) = (frame_percent * self.half_snake_width_f64 * forward_angle_deg.to_radians().cos(),
@@ -438,7 +487,7 @@ impl<'a> SnakeTextureManager<'a> {
             let mut prev = head;
             let next_bit = iter.next();
 
-            println!("frame_percent: {}", frame_percent);
+            log::trace!("frame_percent: {}", frame_percent);
 
             self.draw_animated_snake_head(frame_percent, game, head, next_bit, canvas);
             // self.draw_snake_bit(frame_percent, game, head, bit, None, canvas);
@@ -465,7 +514,7 @@ impl<'a> SnakeTextureManager<'a> {
                 None => (),
             }
         } else {
-            panic!("headless snake!");
+            log::error!("headless snake, nothing to draw");
         }
     }
 
@@ -518,24 +567,28 @@ impl<'a> SnakeTextureManager<'a> {
         // translate rotated surface root point to grid
         let (sx, sy) = (pt.0 + tx as i32, pt.1 + ty as i32);
 
-        println!("%:{}, in:{}, fwd:{:.1}, tgt:{}, t:({:.1},{:.1}), s:({},{})",
-                 frame_percent,
-                 incoming_angle_deg,
-                 forward_angle_deg,
-                 target_angle_deg,
-                 tx,ty,
-                 sx,sy);
-
-        canvas.copy_ex(&self.head,        // texture
-                       None,              // src rect -- None = entire texture
-                       sdl2::rect::Rect::new(sx + self.tile_margin as i32,
-                                             sy + self.tile_margin as i32,
-                                             self.snake_width as u32,
-                                             self.snake_width as u32), // dst rect
-                       forward_angle_deg, // angle of rotation
-                       None,              // center for rotation -- None = dst (or src if dst None)
-                       false,             // flip_horizontal
-                       false);            // flip_vertical
+        log::trace!("%:{}, in:{}, fwd:{:.1}, tgt:{}, t:({:.1},{:.1}), s:({},{})",
+                    frame_percent,
+                    incoming_angle_deg,
+                    forward_angle_deg,
+                    target_angle_deg,
+                    tx,ty,
+                    sx,sy);
+
+        let dst = sdl2::rect::Rect::new(sx + self.tile_margin as i32,
+                                        sy + self.tile_margin as i32,
+                                        self.snake_width as u32,
+                                        self.snake_width as u32);
+
+        for dst in self.wrapped_rects(game, dst) {
+            let _ = canvas.copy_ex(&self.head,        // texture
+                                   None,              // src rect -- None = entire texture
+                                   dst,               // dst rect
+                                   forward_angle_deg, // angle of rotation
+                                   None,              // center for rotation -- None = dst (or src if dst None)
+                                   false,             // flip_horizontal
+                                   false);            // flip_vertical
+        }
 
         // try to draw a box around the rotated one
         let rect = ();
@@ -579,6 +632,8 @@ fn rotate_rect(rect: &sdl2::rect::Rect,
 {
     match direction {
         Direction::Right | Direction::Left => rect.clone(),
+        // only square boards are drawn, see main.rs
+        Direction::UpRight | Direction::DownRight | Direction::DownLeft | Direction::UpLeft => rect.clone(),
         Direction::Down | Direction::Up => sdl2::rect::Rect::new(
             rect.x + rect.w - rect.h,
             rect.y + rect.h - rect.w,
@@ -610,10 +665,10 @@ fn rotate_rect_old(center: &(i32, i32), rect: &sdl2::rect::Rect, direction: &Dir
 
     // find the rotated top left
     let pts: ((i32, i32), (i32, i32)) = match direction {
-        Direction::Up    => (rotated[0], rotated[2]),
-        Direction::Down  => (rotated[2], rotated[0]),
-        Direction::Left  => (rotated[1], rotated[3]),
-        Direction::Right => (rotated[3], rotated[1]),
+        Direction::Up    | Direction::UpRight   => (rotated[0], rotated[2]),
+        Direction::Down  | Direction::DownLeft  => (rotated[2], rotated[0]),
+        Direction::Left  | Direction::UpLeft    => (rotated[1], rotated[3]),
+        Direction::Right | Direction::DownRight => (rotated[3], rotated[1]),
     };
 
     let out = sdl2::rect::Rect::new (pts.0.0,
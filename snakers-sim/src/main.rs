@@ -0,0 +1,355 @@
+/*
+ * Plays a batch of games with a bot and no screen, and sums up how they went. One game per seed,
+ * so the same command line plays the same games again (as long as the bot doesn't roll its own
+ * dice) and two bots, or two versions of the rules, can be compared seed for seed.
+ * */
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use snakers::config::{GameConfig, WallPolicy};
+use snakers::controller::{bot_by_name, BOT_NAMES};
+use snakers::coord::Coord;
+use snakers::direction::Direction;
+use snakers::food::{FoodKind, FoodRule};
+use snakers::game::*;
+use snakers::level::Level;
+use snakers::powerup::{PowerUp, PowerUpRule};
+use snakers::stats::ScoreFormula;
+use snakers::topology::Shape;
+
+struct Options {
+    bot: String,
+    games: u64,
+    first_seed: u64,
+    width: u32,
+    height: u32,
+    shape: Shape,
+    wall_policy: WallPolicy,
+    level: Option<Level>,
+    // the rest of the config, on top of the level; None leaves the default
+    initial_length: Option<i32>,
+    food: Vec<(FoodKind, FoodRule)>,
+    power_ups: Vec<(PowerUp, PowerUpRule)>,
+    power_up_chance: Option<u32>,
+    spawn: Option<Coord>,
+    heading: Option<Direction>,
+    score_formula: Option<ScoreFormula>,
+    turn_queue_depth: Option<usize>,
+    max_ticks: u64,
+    json: bool,
+}
+
+fn usage(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!("usage: snakers-sim [--bot {}] [--games N] [--seed FIRST]", BOT_NAMES.join("|"));
+    eprintln!("                   [--size WIDTHxHEIGHT] [--shape square|hex] [--walls die|wrap|bounce]");
+    eprintln!("                   [--level FILE] [--length N] [--spawn X,Y] [--heading DIRECTION]");
+    eprintln!("                   [--food KIND,WEIGHT,GROWTH,VALUE]... [--power-up KIND,WEIGHT,DURATION]...");
+    eprintln!("                   [--power-up-chance PERCENT] [--score PER_FOOD,PER_TICK,PER_LENGTH]");
+    eprintln!("                   [--turn-queue-depth N]");
+    eprintln!("                   [--max-ticks N] [--json]");
+    std::process::exit(2);
+}
+
+fn parse_args() -> Options {
+    let mut options = Options {
+        bot: "astar".to_string(),
+        games: 100,
+        first_seed: 0,
+        width: 48,
+        height: 18,
+        shape: Shape::Square,
+        wall_policy: WallPolicy::Die,
+        level: None,
+        initial_length: None,
+        food: Vec::new(),
+        power_ups: Vec::new(),
+        power_up_chance: None,
+        spawn: None,
+        heading: None,
+        score_formula: None,
+        turn_queue_depth: None,
+        max_ticks: 100_000,
+        json: false,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage(&format!("{} needs a value", arg)));
+        match arg.as_str() {
+            "--bot" => options.bot = value(),
+            "--games" => options.games = number(&value()),
+            "--seed" => options.first_seed = number(&value()),
+            "--size" => (options.width, options.height) = size(&value()),
+            "--shape" => options.shape = value().parse().unwrap_or_else(|e: String| usage(&e)),
+            "--walls" => options.wall_policy = value().parse().unwrap_or_else(|e: String| usage(&e)),
+            "--level" => options.level = Some(read_level(&value())),
+            "--length" => options.initial_length = Some(number(&value())),
+            "--food" => options.food.push(food(&value())),
+            "--power-up" => options.power_ups.push(power_up(&value())),
+            "--power-up-chance" => options.power_up_chance = Some(number(&value())),
+            "--spawn" => options.spawn = Some(spawn(&value())),
+            "--heading" => options.heading = Some(value().parse().unwrap_or_else(|e: String| usage(&e))),
+            "--score" => options.score_formula = Some(score_formula(&value())),
+            "--turn-queue-depth" => options.turn_queue_depth = Some(number(&value())),
+            "--max-ticks" => options.max_ticks = number(&value()),
+            "--json" => options.json = true,
+            _ => usage(&format!("unknown option '{}'", arg)),
+        }
+    }
+
+    if bot_by_name(&options.bot).is_none() {
+        usage(&format!("unknown bot '{}'", options.bot));
+    }
+
+    // seeds run to first_seed + games - 1, which has to be a seed too
+    if options.first_seed.checked_add(options.games.saturating_sub(1)).is_none() {
+        usage(&format!("{} games from seed {} go past the last seed", options.games, options.first_seed));
+    }
+
+    options
+}
+
+fn number<T: std::str::FromStr>(text: &str) -> T {
+    text.parse().unwrap_or_else(|_| usage(&format!("'{}' isn't a number", text)))
+}
+
+fn size(text: &str) -> (u32, u32) {
+    match text.split_once('x') {
+        Some((width, height)) => (number(width), number(height)),
+        None => usage(&format!("'{}' isn't a size like 48x18", text)),
+    }
+}
+
+// the values in a comma separated list like 10,6,3, which has to have this many
+fn fields(text: &str, count: usize) -> Vec<&str> {
+    let fields: Vec<&str> = text.split(',').collect();
+    if fields.len() != count {
+        usage(&format!("'{}' needs {} values separated by commas", text, count));
+    }
+    fields
+}
+
+fn food(text: &str) -> (FoodKind, FoodRule) {
+    let f = fields(text, 4);
+    let kind = f[0].parse().unwrap_or_else(|e: String| usage(&e));
+    (kind, FoodRule { weight: number(f[1]), growth: number(f[2]), value: number(f[3]) })
+}
+
+fn power_up(text: &str) -> (PowerUp, PowerUpRule) {
+    let f = fields(text, 3);
+    let kind = f[0].parse().unwrap_or_else(|e: String| usage(&e));
+    (kind, PowerUpRule { weight: number(f[1]), duration: number(f[2]) })
+}
+
+fn spawn(text: &str) -> Coord {
+    let f = fields(text, 2);
+    Coord::new(number(f[0]), number(f[1]))
+}
+
+fn score_formula(text: &str) -> ScoreFormula {
+    let f = fields(text, 3);
+    ScoreFormula { per_food: number(f[0]), per_tick: number(f[1]), per_length: number(f[2]) }
+}
+
+fn read_level(path: &str) -> Level {
+    let text = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| usage(&format!("can't read level {}: {}", path, e)));
+    Level::parse(&text).unwrap_or_else(|e| usage(&format!("bad level {}: {}", path, e)))
+}
+
+// how one game went
+#[derive(Serialize)]
+struct GameResult {
+    seed: u64,
+    end: &'static str,
+    length: usize,
+    score: u64,
+    ticks: u64,
+}
+
+#[derive(Serialize)]
+struct Spread {
+    min: u64,
+    max: u64,
+    mean: f64,
+    median: u64,
+}
+
+impl Spread {
+    fn of(mut values: Vec<u64>) -> Self {
+        values.sort_unstable();
+        match (values.first(), values.last()) {
+            (Some(min), Some(max)) => Spread {
+                min: *min,
+                max: *max,
+                // as floats, as the sum of big scores can be past u64::MAX
+                mean: values.iter().map(|v| *v as f64).sum::<f64>() / values.len() as f64,
+                median: values[values.len() / 2],
+            },
+            _ => Spread { min: 0, max: 0, mean: 0.0, median: 0 },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Report {
+    bot: String,
+    games: u64,
+    first_seed: u64,
+    width: u32,
+    height: u32,
+    shape: String,
+    wall_policy: String,
+    initial_length: i32,
+    length: Spread,
+    score: Spread,
+    ticks: Spread,
+    // how many games ended each way
+    ends: BTreeMap<&'static str, u64>,
+    results: Vec<GameResult>,
+}
+
+// names for the ways a game can end, for the report
+fn end_name(cause: GameOverCause) -> &'static str {
+    match cause {
+        GameOverCause::HitWall => "hit-wall",
+        GameOverCause::HitSelf => "hit-self",
+        GameOverCause::Quit => "quit",
+        GameOverCause::BoardCleared => "board-cleared",
+    }
+}
+
+// bots that go round in circles never finish on their own, so they are stopped at max_ticks
+const TIMED_OUT: &str = "timed-out";
+
+fn play(config: &GameConfig, options: &Options, seed: u64) -> GameResult {
+    let mut game = GameState::with_seed(config.clone(), seed).unwrap_or_else(|e| usage(&e.to_string()));
+    // nothing here takes a tick back
+    game.set_history_depth(0);
+    let mut bot = bot_by_name(&options.bot).unwrap(); // parse_args checked the name
+
+    let end = loop {
+        if game.get_tick() >= options.max_ticks {
+            break TIMED_OUT;
+        }
+
+        let input = bot.next_input(&game);
+        if let StateTransition::Stop(outcome) = game.handle_input(input) {
+            break end_name(outcome.cause);
+        }
+        if let StateTransition::Stop(outcome) = game.update_state() {
+            break end_name(outcome.cause);
+        }
+    };
+
+    GameResult {
+        seed,
+        end,
+        length: game.get_snake().get_body().len(),
+        score: game.get_stats().get_score(),
+        ticks: game.get_tick(),
+    }
+}
+
+fn main() {
+    let options = parse_args();
+
+    let mut builder = GameConfig::builder()
+        .size(options.width, options.height)
+        .shape(options.shape)
+        .wall_policy(options.wall_policy);
+
+    if let Some(level) = &options.level {
+        builder = level.apply(builder);
+    }
+
+    // these go after the level, so they win over it
+    if let Some(length) = options.initial_length {
+        builder = builder.initial_length(length);
+    }
+    for (kind, rule) in &options.food {
+        builder = builder.food(*kind, *rule);
+    }
+    for (kind, rule) in &options.power_ups {
+        builder = builder.power_up(*kind, *rule);
+    }
+    if let Some(chance) = options.power_up_chance {
+        builder = builder.power_up_chance(chance);
+    }
+    if let Some(spawn) = options.spawn {
+        builder = builder.spawn(spawn);
+    }
+    if let Some(heading) = options.heading {
+        builder = builder.heading(heading);
+    }
+    if let Some(score_formula) = options.score_formula {
+        builder = builder.score_formula(score_formula);
+    }
+    if let Some(depth) = options.turn_queue_depth {
+        builder = builder.turn_queue_depth(depth);
+    }
+
+    let config = builder.build().unwrap_or_else(|e| usage(&e.to_string()));
+
+    let results: Vec<GameResult> = (0..options.games)
+        .map(|i| play(&config, &options, options.first_seed + i))
+        .collect();
+
+    let mut ends = BTreeMap::new();
+    for result in &results {
+        *ends.entry(result.end).or_insert(0) += 1;
+    }
+
+    let report = Report {
+        bot: options.bot.clone(),
+        games: options.games,
+        first_seed: options.first_seed,
+        width: config.get_width(),
+        height: config.get_height(),
+        shape: config.get_shape().to_string(),
+        wall_policy: config.get_wall_policy().to_string(),
+        initial_length: config.get_initial_length(),
+        length: Spread::of(results.iter().map(|r| r.length as u64).collect()),
+        score: Spread::of(results.iter().map(|r| r.score).collect()),
+        ticks: Spread::of(results.iter().map(|r| r.ticks).collect()),
+        ends,
+        results,
+    };
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        print_table(&report);
+    }
+}
+
+fn print_table(report: &Report) {
+    println!(
+        "{} games of {} on {}x{} {}, walls {}, starting length {}, seeds {} to {}",
+        report.games,
+        report.bot,
+        report.width,
+        report.height,
+        report.shape,
+        report.wall_policy,
+        report.initial_length,
+        report.first_seed,
+        report.first_seed + report.games.saturating_sub(1),
+    );
+    println!();
+
+    println!("{:<8} {:>8} {:>10} {:>8} {:>8}", "", "min", "mean", "median", "max");
+    for (name, spread) in [("length", &report.length), ("score", &report.score), ("ticks", &report.ticks)] {
+        println!("{:<8} {:>8} {:>10.1} {:>8} {:>8}", name, spread.min, spread.mean, spread.median, spread.max);
+    }
+    println!();
+
+    println!("{:<14} {:>8} {:>8}", "ended by", "games", "share");
+    for (end, count) in &report.ends {
+        let share = 100.0 * *count as f64 / report.games.max(1) as f64;
+        println!("{:<14} {:>8} {:>7.1}%", end, count, share);
+    }
+}
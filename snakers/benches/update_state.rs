@@ -0,0 +1,67 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+use snakers::config::{GameConfig, WallPolicy};
+use snakers::game::GameState;
+
+/*
+ * Ticks on a 1000x1000 board. Every tick is the first one of a fresh copy of the same game, made
+ * outside the timing, so the snake's length is the only thing that changes from one run to the
+ * next. Moving should cost the same however long the snake is. No history is kept, so a tick isn't
+ * also paying for the delta that would let it be taken back.
+ *
+ *   cargo bench -p snakers
+ * */
+const SIZE: u32 = 1000;
+const LENGTHS: [i32; 3] = [10, 100, 900];
+
+fn new_game(length: i32) -> GameState {
+    let config = GameConfig::builder()
+        .size(SIZE, SIZE)
+        .initial_length(length)
+        .wall_policy(WallPolicy::Wrap)
+        .build()
+        .unwrap();
+    let mut game = GameState::with_seed(config, 1).unwrap();
+    game.set_history_depth(0);
+    game
+}
+
+fn update_state(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update_state 1000x1000");
+    group.throughput(Throughput::Elements(1));
+
+    for length in LENGTHS {
+        let start = new_game(length);
+        group.bench_with_input(BenchmarkId::new("length", length), &start, |b, start| {
+            // the game goes back out so dropping it isn't timed either
+            b.iter_batched(
+                || start.clone(),
+                |mut game| {
+                    game.update_state();
+                    game
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+// which segment is in a cell, asked of the tail so a list would have to walk the whole snake
+fn index_of(c: &mut Criterion) {
+    let mut group = c.benchmark_group("index_of 1000x1000");
+
+    for length in LENGTHS {
+        let game = new_game(length);
+        let tail = game.get_snake().get_body().back().unwrap().coord;
+        group.bench_with_input(BenchmarkId::new("length", length), &tail, |b, tail| {
+            b.iter(|| game.get_snake().get_body().index_of(black_box(tail)))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, update_state, index_of);
+criterion_main!(benches);
@@ -0,0 +1,232 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+use crate::config::WallPolicy;
+use crate::controller::{is_open, is_safe_move, neighbor, Controller};
+use crate::coord::Coord;
+use crate::direction::Direction;
+use crate::game::{GameState, ItemType};
+use crate::inputtype::InputType;
+use crate::topology::Topology;
+
+/*
+ * Heads for the food along the shortest path A* can find. Cells count as open the way they would
+ * for the next move, so the tail is only a way through when the snake isn't growing. When the food
+ * can't be reached it takes whichever move leaves the most room to move about in, and hopes the
+ * way opens up later.
+ * */
+#[derive(Default)]
+pub struct AStarBot {}
+
+impl Controller for AStarBot {
+    fn next_input(&mut self, game: &GameState) -> InputType {
+        let heading = game.get_heading();
+
+        let direction = match path_to_food(game) {
+            Some(direction) => Some(direction),
+            None => roomiest_move(game),
+        };
+
+        match direction {
+            // already going that way
+            Some(direction) if direction == heading => InputType::Nothing,
+            Some(direction) => InputType::from(direction),
+            None => InputType::Nothing,
+        }
+    }
+}
+
+// the first step of the shortest path to any food, if there is one
+pub(crate) fn path_to_food(game: &GameState) -> Option<Direction> {
+    let head = game.get_snake().get_body().front().unwrap().coord; // all snakes have a head
+    let food = find_food(game);
+    if food.is_empty() {
+        return None;
+    }
+
+    // ordered by estimated total length, then by cells visited so ties go to the one found first
+    let mut open = BinaryHeap::new();
+    // each cell reached, with how far it is from the head and the first step taken to get there
+    let mut reached: HashMap<Coord, (u32, Direction)> = HashMap::new();
+    let mut visited = 0u64;
+
+    // the snake can't turn back on itself, so the first step is never the way it came
+    for &direction in game.get_board().directions() {
+        if direction == game.get_heading().get_opposite() || !is_safe_move(game, direction) {
+            continue;
+        }
+        let at = neighbor(game, &head, direction).unwrap(); // is_safe_move checked it
+        reached.insert(at, (1, direction));
+        open.push(Reverse((1 + estimate(game, &at, &food), visited, at.x, at.y)));
+        visited += 1;
+    }
+
+    while let Some(Reverse((_, _, x, y))) = open.pop() {
+        let at = Coord::new(x, y);
+        let (distance, first) = reached[&at];
+
+        if food.contains(&at) {
+            return Some(first);
+        }
+
+        for &direction in game.get_board().directions() {
+            let next = match neighbor(game, &at, direction) {
+                Some(next) if next != head && is_open(game, &next) => next,
+                _ => continue,
+            };
+            let better = match reached.get(&next) {
+                Some((known, _)) => distance + 1 < *known,
+                None => true,
+            };
+            if better {
+                reached.insert(next, (distance + 1, first));
+                open.push(Reverse((distance + 1 + estimate(game, &next, &food), visited, next.x, next.y)));
+                visited += 1;
+            }
+        }
+    }
+
+    None
+}
+
+// every cell with food on it, in game coordinates
+fn find_food(game: &GameState) -> Vec<Coord> {
+    let mut food = Vec::new();
+    for (y, row) in game.get_world().iter().enumerate() {
+        for (x, item) in row.iter().enumerate() {
+            if let ItemType::Food(_) = item {
+                food.push(game.grid_to_game(&Coord::new(x as i32, y as i32)));
+            }
+        }
+    }
+    food
+}
+
+// the fewest steps to the nearest food if nothing were in the way; A* needs this to never overshoot
+fn estimate(game: &GameState, at: &Coord, food: &[Coord]) -> u32 {
+    let wrap = game.get_wall_policy() == WallPolicy::Wrap;
+    food.iter().map(|f| game.get_board().distance(at, f, wrap)).min().unwrap_or(0)
+}
+
+// the safe move with the most open cells reachable after it
+pub(crate) fn roomiest_move(game: &GameState) -> Option<Direction> {
+    let head = game.get_snake().get_body().front().unwrap().coord;
+
+    game.get_board().directions().iter()
+        .filter(|d| **d != game.get_heading().get_opposite() && is_safe_move(game, **d))
+        .map(|d| (*d, room_from(game, &neighbor(game, &head, *d).unwrap(), &head)))
+        .max_by_key(|(_, room)| *room)
+        .map(|(d, _)| d)
+}
+
+// how many open cells can be reached from here, not going back through the head
+fn room_from(game: &GameState, start: &Coord, head: &Coord) -> usize {
+    let mut seen = HashSet::from([*start]);
+    let mut queue = VecDeque::from([*start]);
+
+    while let Some(at) = queue.pop_front() {
+        for &direction in game.get_board().directions() {
+            if let Some(next) = neighbor(game, &at, direction) {
+                if next != *head && is_open(game, &next) && seen.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    seen.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::game::StateTransition;
+    use crate::level::Level;
+
+    /*
+     * the head is against a wall with a dead end above it and room below, and one cell on the
+     * right is walled off from everything
+     * */
+    const LEVEL: &str = "\
+#########
+##.######
+#o>######
+##.....##
+##.....#.
+##.....##
+#########
+";
+
+    fn level_game(seed: u64) -> GameState {
+        let config = Level::parse(LEVEL).unwrap().apply(GameConfig::builder()).build().unwrap();
+        GameState::with_seed(config, seed).unwrap()
+    }
+
+    // steps to the nearest food from a cell, going only through cells that are open now
+    fn steps_to_food(game: &GameState, from: &Coord) -> Option<u32> {
+        let mut seen = HashMap::from([(*from, 0)]);
+        let mut queue = VecDeque::from([*from]);
+        while let Some(at) = queue.pop_front() {
+            if let Some(ItemType::Food(_)) = game.get(&at) {
+                return Some(seen[&at]);
+            }
+            for &direction in game.get_board().directions() {
+                if let Some(next) = neighbor(game, &at, direction) {
+                    if is_open(game, &next) && !seen.contains_key(&next) {
+                        seen.insert(next, seen[&at] + 1);
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // whatever path_to_food picks starts one of the shortest paths there are
+    fn assert_shortest(game: &GameState) {
+        let head = game.get_snake().get_body().front().unwrap().coord;
+        let steps = |d: Direction| match is_safe_move(game, d) && d != game.get_heading().get_opposite() {
+            true => steps_to_food(game, &neighbor(game, &head, d).unwrap()),
+            false => None,
+        };
+        let best = game.get_board().directions().iter().filter_map(|d| steps(*d)).min();
+        assert_eq!(path_to_food(game).and_then(steps), best, "tick {}", game.get_tick());
+    }
+
+    #[test]
+    fn takes_a_shortest_path_to_food() {
+        for seed in 0..20 {
+            assert_shortest(&level_game(seed));
+        }
+
+        // and all the way through a game on an open board
+        let config = GameConfig::builder().size(12, 10).wall_policy(WallPolicy::Wrap).build().unwrap();
+        let mut game = GameState::with_seed(config, 3).unwrap();
+        let mut bot = AStarBot::default();
+        for _ in 0..300 {
+            assert_shortest(&game);
+            game.handle_input(bot.next_input(&game));
+            if let StateTransition::Stop(_) = game.update_state() {
+                break;
+            }
+        }
+        assert!(game.get_stats().get_food_eaten() > 5);
+    }
+
+    #[test]
+    fn falls_back_to_the_roomiest_move() {
+        // the first game whose food went in the walled off cell
+        let walled_off = |g: &GameState| matches!(g.get(&g.grid_to_game(&Coord::new(8, 4))), Some(ItemType::Food(_)));
+        let mut game = (0..).map(level_game).find(walled_off).unwrap();
+
+        assert_eq!(path_to_food(&game), None);
+        assert_eq!(roomiest_move(&game), Some(Direction::Down));
+        assert_eq!(AStarBot::default().next_input(&game), InputType::Down);
+
+        // and it keeps going where there is room
+        game.handle_input(InputType::Down);
+        game.update_state();
+        assert_eq!(roomiest_move(&game).map(|d| is_safe_move(&game, d)), Some(true));
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+
+use crate::coord::Coord;
+use crate::game::CoordWithDirection;
+use crate::topology::{Board, Topology};
+
+/*
+ * The snake's segments, head first. Moving only ever adds a head and drops the tail, so they live
+ * in a ring buffer (a VecDeque) and both ends are O(1).
+ *
+ * Every segment gets a serial number when it goes on the front, one more than the last, so the
+ * one at index i from the head has serial (pushed - 1 - i). A per-cell index holds the serial of
+ * the segment in each cell, which makes "is the snake here" and "how far back from the head is
+ * this" O(1) as well. A ghost can put two segments in the same cell; the index keeps the newer
+ * one, which is also the one that leaves last, so dropping the older one leaves the cell alone.
+ *
+ * Cells here are in game coordinates, the same as the segments.
+ * */
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Body {
+    segments: VecDeque<CoordWithDirection>,
+    // segments ever put on the front
+    pushed: u64,
+    // where game coordinates start, and how many cells a row has
+    origin: Coord,
+    width: usize,
+    height: usize,
+    // the serial of the segment nearest the head in each cell, row by row
+    cells: Vec<Option<u64>>,
+}
+
+impl Body {
+    pub(crate) fn new(board: &Board) -> Self {
+        let (width, height) = (board.get_width() as usize, board.get_height() as usize);
+        Body {
+            segments: VecDeque::new(),
+            pushed: 0,
+            origin: Coord::new(board.get_xrange().0, board.get_yrange().0),
+            width,
+            height,
+            cells: vec![None; width * height],
+        }
+    }
+
+    fn slot(&self, at: &Coord) -> Option<usize> {
+        let (x, y) = (at.x - self.origin.x, at.y - self.origin.y);
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some(y as usize * self.width + x as usize)
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn front(&self) -> Option<&CoordWithDirection> {
+        self.segments.front()
+    }
+
+    pub fn back(&self) -> Option<&CoordWithDirection> {
+        self.segments.back()
+    }
+
+    // 0 is the head
+    pub fn get(&self, index: usize) -> Option<&CoordWithDirection> {
+        self.segments.get(index)
+    }
+
+    pub fn iter(&self) -> std::collections::vec_deque::Iter<'_, CoordWithDirection> {
+        self.segments.iter()
+    }
+
+    // how far back from the head the segment in this cell is (0 for the head), nearest if there are two
+    pub fn index_of(&self, at: &Coord) -> Option<usize> {
+        let serial = self.cells[self.slot(at)?]?;
+        Some((self.pushed - 1 - serial) as usize)
+    }
+
+    pub fn contains(&self, at: &Coord) -> bool {
+        self.index_of(at).is_some()
+    }
+
+    // returns what the cell's index had before, for taking it back with pop_front
+    pub(crate) fn push_front(&mut self, segment: CoordWithDirection) -> Option<u64> {
+        // the config and the move rules keep the snake on the board
+        let slot = self.slot(&segment.coord).unwrap();
+        let replaced = self.cells[slot].replace(self.pushed);
+        self.pushed += 1;
+        self.segments.push_front(segment);
+        replaced
+    }
+
+    // takes back the last push_front
+    pub(crate) fn pop_front(&mut self, replaced: Option<u64>) -> Option<CoordWithDirection> {
+        let segment = self.segments.pop_front()?;
+        let slot = self.slot(&segment.coord).unwrap();
+        self.cells[slot] = replaced;
+        self.pushed -= 1;
+        Some(segment)
+    }
+
+    pub(crate) fn pop_back(&mut self) -> Option<CoordWithDirection> {
+        let segment = self.segments.pop_back()?;
+        let serial = self.pushed - 1 - self.segments.len() as u64;
+
+        // a newer segment in the same cell keeps it
+        let slot = self.slot(&segment.coord).unwrap();
+        if self.cells[slot] == Some(serial) {
+            self.cells[slot] = None;
+        }
+
+        Some(segment)
+    }
+
+    // takes back the last pop_back; if the cell was kept by a newer segment then, it still is
+    pub(crate) fn push_back(&mut self, segment: CoordWithDirection) {
+        let slot = self.slot(&segment.coord).unwrap();
+        self.segments.push_back(segment);
+        let serial = self.pushed - self.segments.len() as u64;
+        if self.cells[slot].is_none() {
+            self.cells[slot] = Some(serial);
+        }
+    }
+
+    // only the directions should change through these; a segment's cell is fixed once it's on
+    pub(crate) fn front_mut(&mut self) -> Option<&mut CoordWithDirection> {
+        self.segments.front_mut()
+    }
+
+    pub(crate) fn back_mut(&mut self) -> Option<&mut CoordWithDirection> {
+        self.segments.back_mut()
+    }
+
+    // whether the index covers this board and says what the segments do, for GameState::validate
+    pub(crate) fn is_indexed(&self, board: &Board) -> bool {
+        let mut expected = Body::new(board);
+        if (self.origin, self.width, self.height) != (expected.origin, expected.width, expected.height)
+            || self.cells.len() != expected.cells.len()
+            || self.pushed < self.segments.len() as u64
+        {
+            return false;
+        }
+
+        // tail first, so where two segments share a cell the one nearer the head is left
+        for (i, segment) in self.segments.iter().enumerate().rev() {
+            match expected.slot(&segment.coord) {
+                Some(slot) => expected.cells[slot] = Some(self.pushed - 1 - i as u64),
+                None => return false,
+            }
+        }
+
+        expected.cells == self.cells
+    }
+}
+
+// only the segments count, so a body hashes the same however it was stored
+impl Hash for Body {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.segments.hash(state);
+    }
+}
+
+impl<'a> IntoIterator for &'a Body {
+    type Item = &'a CoordWithDirection;
+    type IntoIter = std::collections::vec_deque::Iter<'a, CoordWithDirection>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.segments.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{GameConfig, WallPolicy};
+    use crate::direction::Direction;
+    use crate::food::{FoodKind, FoodRule};
+    use crate::game::{GameState, StateTransition};
+    use crate::inputtype::InputType;
+    use crate::topology::Shape;
+
+    fn board() -> Board {
+        Board::new(Shape::Square, 4, 3)
+    }
+
+    // a segment at x, y from the board's top left corner; which way it points doesn't matter here
+    fn segment(board: &Board, x: i32, y: i32) -> CoordWithDirection {
+        let coord = Coord::new(board.get_xrange().0 + x, board.get_yrange().0 + y);
+        CoordWithDirection { dir_next: Direction::Right, coord, dir_prev: Direction::Left }
+    }
+
+    #[test]
+    fn push_and_pop() {
+        let board = board();
+        let mut body = Body::new(&board);
+        let (a, b, c) = (segment(&board, 0, 0), segment(&board, 1, 0), segment(&board, 2, 0));
+
+        assert_eq!(body.push_front(a), None);
+        assert_eq!(body.push_front(b), None);
+        assert_eq!(body.push_front(c), None);
+        assert_eq!((body.len(), body.front(), body.back()), (3, Some(&c), Some(&a)));
+        assert_eq!(body.index_of(&c.coord), Some(0));
+        assert_eq!(body.index_of(&b.coord), Some(1));
+        assert_eq!(body.index_of(&a.coord), Some(2));
+        assert!(body.is_indexed(&board));
+
+        assert_eq!(body.pop_back(), Some(a));
+        assert!(!body.contains(&a.coord));
+        assert!(body.is_indexed(&board));
+
+        // both undone, back to where it was
+        body.push_back(a);
+        assert_eq!(body.pop_front(None), Some(c));
+        assert!(!body.contains(&c.coord));
+        assert_eq!([body.index_of(&b.coord), body.index_of(&a.coord)], [Some(0), Some(1)]);
+        assert!(body.is_indexed(&board));
+
+        assert_eq!(body.pop_back(), Some(a));
+        assert_eq!(body.pop_back(), Some(b));
+        assert_eq!(body.pop_back(), None);
+        assert!(body.is_empty());
+        assert!(body.is_indexed(&board));
+    }
+
+    #[test]
+    fn ring_wraps_around() {
+        let board = board();
+        let mut body = Body::new(&board);
+        // round the edge of the board, 10 cells
+        let lap: Vec<_> = (0..4)
+            .map(|x| (x, 0))
+            .chain((1..3).map(|y| (3, y)))
+            .chain((0..3).rev().map(|x| (x, 2)))
+            .chain([(0, 1)])
+            .map(|(x, y)| segment(&board, x, y))
+            .collect();
+
+        for s in &lap[..3] {
+            body.push_front(*s);
+        }
+        // many times round the buffer, which never holds more than four
+        for i in 3..103 {
+            body.push_front(lap[i % lap.len()]);
+            body.pop_back();
+            assert_eq!(body.len(), 3);
+            for back in 0..3 {
+                assert_eq!(body.get(back), Some(&lap[(i - back) % lap.len()]));
+                assert_eq!(body.index_of(&lap[(i - back) % lap.len()].coord), Some(back));
+            }
+            assert!(!body.contains(&lap[(i - 3) % lap.len()].coord));
+            assert!(body.is_indexed(&board));
+        }
+    }
+
+    #[test]
+    fn indexed_after_growing_and_shrinking() {
+        let board = board();
+        let mut body = Body::new(&board);
+
+        for x in 0..4 {
+            body.push_front(segment(&board, x, 1));
+            assert!(body.is_indexed(&board));
+        }
+        for x in (0..4).rev() {
+            body.push_front(segment(&board, x, 2));
+            assert!(body.is_indexed(&board));
+        }
+        assert_eq!(body.len(), 8);
+
+        while body.len() > 2 {
+            body.pop_back();
+            assert!(body.is_indexed(&board));
+        }
+        assert_eq!(body.index_of(&segment(&board, 0, 2).coord), Some(0));
+        assert_eq!(body.index_of(&segment(&board, 1, 2).coord), Some(1));
+        assert!(!body.contains(&segment(&board, 3, 1).coord));
+
+        // a ghost on top of itself: the cell has the newer segment, and keeps it when the older goes
+        let under = *body.back().unwrap();
+        let replaced = body.push_front(under);
+        assert_eq!(body.index_of(&under.coord), Some(0));
+        assert!(body.is_indexed(&board));
+        body.pop_back();
+        assert_eq!(body.index_of(&under.coord), Some(0));
+        assert!(body.is_indexed(&board));
+
+        // and undoing both puts the older one back
+        body.push_back(under);
+        assert_eq!(body.index_of(&under.coord), Some(0));
+        body.pop_front(replaced);
+        assert_eq!(body.index_of(&under.coord), Some(1));
+        assert!(body.is_indexed(&board));
+    }
+
+    #[test]
+    fn head_into_the_cell_the_tail_leaves() {
+        let board = board();
+        let mut body = Body::new(&board);
+        // a square of four, the tail next to the head
+        let square = [segment(&board, 1, 0), segment(&board, 2, 0), segment(&board, 2, 1), segment(&board, 1, 1)];
+        for s in &square {
+            body.push_front(*s);
+        }
+
+        // the game moves the head before the tail, so for a moment both are in the cell
+        body.push_front(square[0]);
+        assert_eq!(body.len(), 5);
+        assert_eq!(body.index_of(&square[0].coord), Some(0));
+        assert_eq!(body.pop_back(), Some(square[0]));
+        assert_eq!(body.index_of(&square[0].coord), Some(0));
+        assert_eq!(body.index_of(&square[1].coord), Some(3));
+        assert!(body.is_indexed(&board));
+
+        // and in a game that isn't growing the snake can go round after its tail
+        let mut builder = GameConfig::builder()
+            .size(10, 8)
+            .wall_policy(WallPolicy::Die)
+            .initial_length(4)
+            .heading(Direction::Right);
+        for kind in FoodKind::ALL {
+            let weight = GameConfig::default().get_food().get(kind).weight;
+            builder = builder.food(kind, FoodRule { weight, growth: 0, value: 1 });
+        }
+        let mut game = GameState::with_seed(builder.build().unwrap(), 3).unwrap();
+        // up and left from heading right, so going down next is into the tail
+        for input in [InputType::Up, InputType::Left] {
+            game.handle_input(input);
+            assert!(matches!(game.update_state(), StateTransition::Continue));
+        }
+        let tail = game.get_snake().get_body().back().unwrap().coord;
+        game.handle_input(InputType::Down);
+        assert!(matches!(game.update_state(), StateTransition::Continue));
+        let body = game.get_snake().get_body();
+        assert_eq!((body.len(), body.front().unwrap().coord), (4, tail));
+        assert_eq!(body.index_of(&tail), Some(0));
+        assert!(body.is_indexed(game.get_board()));
+    }
+}
@@ -0,0 +1,522 @@
+use crate::coord::Coord;
+use crate::direction::Direction;
+use crate::food::{FoodKind, FoodRule, FoodTable};
+use crate::powerup::{PowerUp, PowerUpRule, PowerUpTable};
+use crate::speed::SpeedCurve;
+use crate::stats::ScoreFormula;
+use crate::topology::{Board, Shape, Topology};
+
+const DEFAULT_WIDTH: u32 = 24;
+const DEFAULT_HEIGHT: u32 = 15;
+const DEFAULT_INITIAL_SNAKE_LENGTH: i32 = 7;
+const DEFAULT_TURN_QUEUE_DEPTH: usize = 3;
+
+// a snake needs a distinct head and tail
+pub(crate) const MINIMUM_SNAKE_LENGTH: i32 = 2;
+
+pub(crate) fn make_coordinate_range(size: u32) -> (i32, i32) {
+    match size % 2 {
+        0 => {
+            let half = (size / 2) as i32;
+            (-half, half - 1)
+        }
+        _ => {
+            let half = ((size - 1) / 2) as i32;
+            (-half, half)
+        }
+    }
+}
+
+// what happens when the head runs into the edge of the board
+#[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WallPolicy {
+    // the game ends
+    Die,
+    // the head comes back in from the opposite edge
+    Wrap,
+    // the head turns to run along the wall
+    Bounce,
+}
+
+impl std::fmt::Display for WallPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WallPolicy::Die => write!(f, "die"),
+            WallPolicy::Wrap => write!(f, "wrap"),
+            WallPolicy::Bounce => write!(f, "bounce"),
+        }
+    }
+}
+
+impl std::str::FromStr for WallPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "die" => Ok(WallPolicy::Die),
+            "wrap" => Ok(WallPolicy::Wrap),
+            "bounce" => Ok(WallPolicy::Bounce),
+            _ => Err(format!("unknown wall policy '{}' (expected die, wrap or bounce)", s)),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum ConfigError {
+    EmptyBoard,
+    SnakeTooShort(i32),
+    NegativeGrowth(i32),
+    SpawnOutOfBounds(Coord),
+    SnakeDoesNotFit,
+    WallOutOfBounds(Coord),
+    SnakeOnWall(Coord),
+    NoRoomForFood,
+    NoTurnQueue,
+    NoFood,
+    BadPowerUpChance(u32),
+    BadSpeedCurve(SpeedCurve),
+    BadHeading(Direction, Shape),
+    HexWrapOddHeight(u32),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConfigError::EmptyBoard => write!(f, "board must be at least 1x1"),
+            ConfigError::SnakeTooShort(length) => write!(
+                f,
+                "initial snake length {} is shorter than {}",
+                length, MINIMUM_SNAKE_LENGTH
+            ),
+            ConfigError::NegativeGrowth(growth) => {
+                write!(f, "regular food growth {} is negative", growth)
+            }
+            ConfigError::SpawnOutOfBounds(at) => write!(f, "spawn {} is off the board", at),
+            ConfigError::SnakeDoesNotFit => {
+                write!(f, "initial snake runs off the board behind its spawn")
+            }
+            ConfigError::WallOutOfBounds(at) => write!(f, "wall {} is off the board", at),
+            ConfigError::SnakeOnWall(at) => write!(f, "initial snake overlaps the wall at {}", at),
+            ConfigError::NoRoomForFood => write!(f, "initial snake leaves no room for food"),
+            ConfigError::NoTurnQueue => write!(f, "turn queue depth must be at least 1"),
+            ConfigError::NoFood => write!(f, "every food kind has a weight of 0"),
+            ConfigError::BadPowerUpChance(chance) => {
+                write!(f, "power-up chance {}% is more than 100%", chance)
+            }
+            ConfigError::BadSpeedCurve(curve) => {
+                write!(f, "speed curve '{}' has a 0 where it can't", curve)
+            }
+            ConfigError::BadHeading(heading, shape) => {
+                write!(f, "a snake on a {} board can't head {}", shape, heading)
+            }
+            ConfigError::HexWrapOddHeight(height) => {
+                write!(f, "hex rows only wrap top to bottom when there's an even number of them, not {}", height)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/*
+ * Rules a GameState is created with. Build one with GameConfig::builder(); the builder checks the
+ * values fit together so GameState never has to.
+ *
+ * The spawn is where the head starts, in game coordinates (the board center is (0, 0)). The rest
+ * of the snake is laid out straight behind the head, opposite to the heading. Walls are also in
+ * game coordinates; a level file is the usual way to come up with them (see level.rs).
+ *
+ * The shape says how the cells connect (see topology.rs). The heading has to be one of the ways
+ * that shape of board goes; left out, it is up on a square board and right on a hex one.
+ *
+ * Turns pressed faster than the snake moves wait in a queue, one taken per tick, so a quick
+ * Up, Left does a tight U-turn instead of only the Left counting. turn_queue_depth is how many can
+ * wait; presses beyond that are dropped.
+ *
+ * The food table says how often each kind of food turns up and what it does (see food.rs).
+ * Regular food can't shrink the snake; the other kinds can do what they like. Power-ups work the
+ * same way (see powerup.rs). The speed curve says how fast the game should run (see speed.rs).
+ * */
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameConfig {
+    width: u32,
+    height: u32,
+    shape: Shape,
+    initial_length: i32,
+    food: FoodTable,
+    power_ups: PowerUpTable,
+    speed: SpeedCurve,
+    spawn: Coord,
+    heading: Direction,
+    wall_policy: WallPolicy,
+    score_formula: ScoreFormula,
+    walls: Vec<Coord>,
+    turn_queue_depth: usize,
+}
+
+impl GameConfig {
+    pub fn builder() -> GameConfigBuilder {
+        GameConfigBuilder::new()
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_shape(&self) -> Shape {
+        self.shape
+    }
+
+    // the board this config plays on, with its coordinate ranges
+    pub fn get_board(&self) -> Board {
+        Board::new(self.shape, self.width, self.height)
+    }
+
+    pub fn get_initial_length(&self) -> i32 {
+        self.initial_length
+    }
+
+    pub fn get_food(&self) -> &FoodTable {
+        &self.food
+    }
+
+    pub fn get_power_ups(&self) -> &PowerUpTable {
+        &self.power_ups
+    }
+
+    pub fn get_speed(&self) -> SpeedCurve {
+        self.speed
+    }
+
+    pub fn get_spawn(&self) -> Coord {
+        self.spawn
+    }
+
+    pub fn get_heading(&self) -> Direction {
+        self.heading
+    }
+
+    pub fn get_wall_policy(&self) -> WallPolicy {
+        self.wall_policy
+    }
+
+    pub fn get_score_formula(&self) -> ScoreFormula {
+        self.score_formula
+    }
+
+    pub fn get_walls(&self) -> &[Coord] {
+        &self.walls
+    }
+
+    pub fn get_turn_queue_depth(&self) -> usize {
+        self.turn_queue_depth
+    }
+
+    // a builder that makes this config again, to start a variation from
+    pub fn to_builder(&self) -> GameConfigBuilder {
+        GameConfigBuilder {
+            width: self.width,
+            height: self.height,
+            shape: self.shape,
+            initial_length: self.initial_length,
+            food: self.food,
+            power_ups: self.power_ups,
+            speed: self.speed,
+            spawn: Some(self.spawn),
+            heading: Some(self.heading),
+            wall_policy: self.wall_policy,
+            score_formula: self.score_formula,
+            walls: self.walls.clone(),
+            turn_queue_depth: self.turn_queue_depth,
+        }
+    }
+
+    /*
+     * a config from the builder is always valid; one that was deserialized might not be, so
+     * GameState checks again before using it.
+     * */
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.to_builder().build().map(|_| ())
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfigBuilder::new().build().unwrap() // the defaults are valid
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GameConfigBuilder {
+    width: u32,
+    height: u32,
+    shape: Shape,
+    initial_length: i32,
+    food: FoodTable,
+    power_ups: PowerUpTable,
+    speed: SpeedCurve,
+    // None spawns the snake in the middle of the edge it is heading away from
+    spawn: Option<Coord>,
+    // None heads the way Shape::default_heading says
+    heading: Option<Direction>,
+    wall_policy: WallPolicy,
+    score_formula: ScoreFormula,
+    walls: Vec<Coord>,
+    turn_queue_depth: usize,
+}
+
+impl Default for GameConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameConfigBuilder {
+    pub fn new() -> Self {
+        GameConfigBuilder {
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            shape: Shape::Square,
+            initial_length: DEFAULT_INITIAL_SNAKE_LENGTH,
+            food: FoodTable::default(),
+            power_ups: PowerUpTable::default(),
+            speed: SpeedCurve::default(),
+            spawn: None,
+            heading: None,
+            wall_policy: WallPolicy::Die,
+            score_formula: ScoreFormula::default(),
+            walls: Vec::new(),
+            turn_queue_depth: DEFAULT_TURN_QUEUE_DEPTH,
+        }
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn shape(mut self, shape: Shape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    pub fn initial_length(mut self, initial_length: i32) -> Self {
+        self.initial_length = initial_length;
+        self
+    }
+
+    pub fn food(mut self, kind: FoodKind, rule: FoodRule) -> Self {
+        self.food.set(kind, rule);
+        self
+    }
+
+    pub fn power_up(mut self, kind: PowerUp, rule: PowerUpRule) -> Self {
+        self.power_ups.set(kind, rule);
+        self
+    }
+
+    // percent chance of a power-up turning up when food is eaten, 0 turns them off
+    pub fn power_up_chance(mut self, chance: u32) -> Self {
+        self.power_ups.set_chance(chance);
+        self
+    }
+
+    pub fn speed(mut self, speed: SpeedCurve) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn spawn(mut self, spawn: Coord) -> Self {
+        self.spawn = Some(spawn);
+        self
+    }
+
+    pub fn heading(mut self, heading: Direction) -> Self {
+        self.heading = Some(heading);
+        self
+    }
+
+    pub fn wall_policy(mut self, wall_policy: WallPolicy) -> Self {
+        self.wall_policy = wall_policy;
+        self
+    }
+
+    pub fn score_formula(mut self, score_formula: ScoreFormula) -> Self {
+        self.score_formula = score_formula;
+        self
+    }
+
+    pub fn walls(mut self, walls: Vec<Coord>) -> Self {
+        self.walls = walls;
+        self
+    }
+
+    pub fn turn_queue_depth(mut self, turn_queue_depth: usize) -> Self {
+        self.turn_queue_depth = turn_queue_depth;
+        self
+    }
+
+    pub fn build(mut self) -> Result<GameConfig, ConfigError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(ConfigError::EmptyBoard);
+        }
+
+        if self.initial_length < MINIMUM_SNAKE_LENGTH {
+            return Err(ConfigError::SnakeTooShort(self.initial_length));
+        }
+
+        let regular = self.food.get(FoodKind::Regular).growth;
+        if regular < 0 {
+            return Err(ConfigError::NegativeGrowth(regular));
+        }
+
+        if self.food.total_weight() == 0 {
+            return Err(ConfigError::NoFood);
+        }
+
+        if self.power_ups.get_chance() > 100 {
+            return Err(ConfigError::BadPowerUpChance(self.power_ups.get_chance()));
+        }
+
+        if !self.speed.is_valid() {
+            return Err(ConfigError::BadSpeedCurve(self.speed));
+        }
+
+        if self.turn_queue_depth == 0 {
+            return Err(ConfigError::NoTurnQueue);
+        }
+
+        let board = Board::new(self.shape, self.width, self.height);
+
+        let heading = self.heading.unwrap_or(self.shape.default_heading());
+        if !board.has_direction(heading) {
+            return Err(ConfigError::BadHeading(heading, self.shape));
+        }
+
+        if self.shape == Shape::Hex && self.wall_policy == WallPolicy::Wrap && self.height % 2 == 1 {
+            return Err(ConfigError::HexWrapOddHeight(self.height));
+        }
+
+        let behind = heading.get_opposite();
+
+        let spawn = match self.spawn {
+            Some(spawn) => {
+                if !board.in_bounds(&spawn) {
+                    return Err(ConfigError::SpawnOutOfBounds(spawn));
+                }
+                spawn
+            }
+            None => {
+                // the edge cell behind the middle of the board, so heading up leaves the bottom
+                // row free like the original layout did
+                let mut edge = Coord::new(0, 0);
+                while board.in_bounds(&board.neighbor(&edge, behind)) {
+                    edge = board.neighbor(&edge, behind);
+                }
+                (0..self.initial_length).fold(edge, |at, _| board.neighbor(&at, heading))
+            }
+        };
+
+        if !board.in_bounds(&spawn) {
+            return Err(ConfigError::SnakeDoesNotFit);
+        }
+
+        // the rest of the snake goes back from the head a neighbor at a time
+        let mut body = vec![spawn];
+        for _ in 1..self.initial_length {
+            let next = board.neighbor(body.last().unwrap(), behind);
+            if !board.in_bounds(&next) {
+                return Err(ConfigError::SnakeDoesNotFit);
+            }
+            body.push(next);
+        }
+
+        if let Some(wall) = self.walls.iter().find(|w| !board.in_bounds(w)) {
+            return Err(ConfigError::WallOutOfBounds(*wall));
+        }
+
+        self.walls.sort_by_key(|w| (w.y, w.x));
+        self.walls.dedup();
+
+        if let Some(at) = body.iter().find(|at| self.walls.contains(at)) {
+            return Err(ConfigError::SnakeOnWall(*at));
+        }
+
+        let open_cells = ((self.width as u64) * (self.height as u64)) - self.walls.len() as u64;
+        if (self.initial_length as u64) >= open_cells {
+            return Err(ConfigError::NoRoomForFood);
+        }
+
+        Ok(GameConfig {
+            width: self.width,
+            height: self.height,
+            shape: self.shape,
+            initial_length: self.initial_length,
+            food: self.food,
+            power_ups: self.power_ups,
+            speed: self.speed,
+            spawn,
+            heading,
+            wall_policy: self.wall_policy,
+            score_formula: self.score_formula,
+            walls: self.walls,
+            turn_queue_depth: self.turn_queue_depth,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder() -> GameConfigBuilder {
+        GameConfig::builder().size(10, 8).initial_length(3).heading(Direction::Right)
+    }
+
+    #[test]
+    fn build_rejects_bad_configs() {
+        let none = FoodRule { weight: 0, growth: 1, value: 1 };
+        let no_food = FoodKind::ALL.iter().fold(builder(), |b, kind| b.food(*kind, none));
+        let shrinking = FoodRule { weight: 1, growth: -1, value: 1 };
+        let stopped = SpeedCurve::Constant { interval_ms: 0 };
+        let cases = [
+            (builder().size(0, 8), ConfigError::EmptyBoard),
+            (builder().initial_length(1), ConfigError::SnakeTooShort(1)),
+            (builder().food(FoodKind::Regular, shrinking), ConfigError::NegativeGrowth(-1)),
+            (no_food, ConfigError::NoFood),
+            (builder().power_up_chance(101), ConfigError::BadPowerUpChance(101)),
+            (builder().speed(stopped), ConfigError::BadSpeedCurve(stopped)),
+            (builder().turn_queue_depth(0), ConfigError::NoTurnQueue),
+            (builder().heading(Direction::UpRight), ConfigError::BadHeading(Direction::UpRight, Shape::Square)),
+            (builder().shape(Shape::Hex).size(10, 7).wall_policy(WallPolicy::Wrap), ConfigError::HexWrapOddHeight(7)),
+            (builder().spawn(Coord::new(5, 0)), ConfigError::SpawnOutOfBounds(Coord::new(5, 0))),
+            (builder().initial_length(11), ConfigError::SnakeDoesNotFit),
+            (builder().spawn(Coord::new(-4, 0)), ConfigError::SnakeDoesNotFit),
+            (builder().walls(vec![Coord::new(0, 4)]), ConfigError::WallOutOfBounds(Coord::new(0, 4))),
+            (builder().walls(vec![Coord::new(-3, 0)]), ConfigError::SnakeOnWall(Coord::new(-3, 0))),
+            (builder().size(3, 1).spawn(Coord::new(1, 0)), ConfigError::NoRoomForFood),
+        ];
+        for (builder, error) in cases {
+            assert_eq!(builder.build().err(), Some(error));
+        }
+    }
+
+    #[test]
+    fn build_tidies_what_it_keeps() {
+        let walls = vec![Coord::new(2, 1), Coord::new(-3, -2), Coord::new(2, 1)];
+        let config = builder().walls(walls).build().unwrap();
+        assert_eq!(config.get_walls(), &[Coord::new(-3, -2), Coord::new(2, 1)]);
+
+        // the default spawn is as far in from the edge behind it as the snake is long
+        assert_eq!(config.get_spawn(), Coord::new(-2, 0));
+        assert_eq!(builder().size(4, 1).build().unwrap().get_spawn(), Coord::new(1, 0));
+        assert_eq!(config.to_builder().build().unwrap(), config);
+    }
+}
@@ -0,0 +1,151 @@
+use rand::seq::SliceRandom;
+
+use crate::astar::AStarBot;
+use crate::config::WallPolicy;
+use crate::coord::Coord;
+use crate::hamiltonian::HamiltonianBot;
+use crate::direction::Direction;
+use crate::game::{GameState, ItemType};
+use crate::inputtype::InputType;
+use crate::topology::Topology;
+
+/*
+ * Whoever is playing: a person at a keyboard, a bot, anything that can pick a move. Frontends ask
+ * the controller for one input per tick, just before the game is updated, so every kind of player
+ * goes through the same path (and into the same replays).
+ * */
+pub trait Controller {
+    // the input for the coming tick, looking at the game as it is now
+    fn next_input(&mut self, game: &GameState) -> InputType;
+
+    // a key the player pressed since the last tick; controllers that don't listen ignore it
+    fn key_pressed(&mut self, _input: InputType) {}
+}
+
+// the names bot_by_name knows, for usage messages
+pub const BOT_NAMES: &[&str] = &["random", "astar", "hamiltonian"];
+
+// one of the bots that come with the game, by the name a command line would use
+pub fn bot_by_name(name: &str) -> Option<Box<dyn Controller>> {
+    match name {
+        "random" => Some(Box::new(RandomBot::default())),
+        "astar" => Some(Box::new(AStarBot::default())),
+        "hamiltonian" => Some(Box::new(HamiltonianBot::default())),
+        _ => None,
+    }
+}
+
+// whether the head can go this way next tick without the game ending
+pub fn is_safe_move(game: &GameState, direction: Direction) -> bool {
+    let head = game.get_snake().get_body().front().unwrap().coord; // all snakes have a head
+    match neighbor(game, &head, direction) {
+        Some(target) => is_open(game, &target),
+        None => false,
+    }
+}
+
+// the cell one step away, across the edge if the walls wrap; None when the edge is in the way
+pub(crate) fn neighbor(game: &GameState, at: &Coord, direction: Direction) -> Option<Coord> {
+    let target = game.get_board().neighbor(at, direction);
+
+    if game.in_bounds(&target) {
+        return Some(target);
+    }
+
+    match game.get_wall_policy() {
+        WallPolicy::Wrap => Some(game.wrap(&target)),
+        // bouncing might work out, but it isn't this move any more
+        WallPolicy::Die | WallPolicy::Bounce => None,
+    }
+}
+
+// whether the head could be in this cell after the next tick
+pub(crate) fn is_open(game: &GameState, at: &Coord) -> bool {
+    match game.get(at) {
+        Some(ItemType::Nothing | ItemType::Food(_) | ItemType::PowerUp(_)) => true,
+        // the tail moves out of the way unless the snake is growing
+        Some(ItemType::SnakeTail) => game.get_snake().get_growing() <= 0,
+        _ => false,
+    }
+}
+
+// wanders about at random, but never straight into something when there is a way out
+#[derive(Default)]
+pub struct RandomBot {}
+
+impl Controller for RandomBot {
+    fn next_input(&mut self, game: &GameState) -> InputType {
+        let heading = game.get_heading();
+        let board = game.get_board();
+
+        let mut choices = vec![heading, board.turn(heading, true), board.turn(heading, false)];
+        choices.retain(|d| is_safe_move(game, *d));
+
+        // mostly carry on, so it doesn't just wiggle in place
+        if choices.contains(&heading) && rand::random::<f64>() < 0.7 {
+            return InputType::Nothing;
+        }
+
+        match choices.choose(&mut rand::thread_rng()) {
+            Some(direction) => InputType::from(*direction),
+            None => InputType::Nothing,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::level::Level;
+
+    fn level_game(level: &str, policy: WallPolicy) -> GameState {
+        let builder = Level::parse(level).unwrap().apply(GameConfig::builder());
+        GameState::with_seed(builder.wall_policy(policy).build().unwrap(), 0).unwrap()
+    }
+
+    #[test]
+    fn bot_by_name_knows_every_listed_name() {
+        for name in BOT_NAMES {
+            assert!(bot_by_name(name).is_some(), "{}", name);
+        }
+        assert!(bot_by_name("nobody").is_none());
+    }
+
+    #[test]
+    fn safe_moves_keep_off_walls_and_the_body() {
+        let game = level_game("#######\n###.###\n#oo>..#\n#######\n", WallPolicy::Die);
+        assert!(is_safe_move(&game, Direction::Up));
+        assert!(is_safe_move(&game, Direction::Right));
+        assert!(!is_safe_move(&game, Direction::Down));
+        assert!(!is_safe_move(&game, Direction::Left));
+
+        // the tail moves out of the way in time
+        let short = level_game("######\n##.###\n#o>..#\n######\n", WallPolicy::Die);
+        assert!(is_safe_move(&short, Direction::Left));
+    }
+
+    #[test]
+    fn safe_moves_cross_the_edge_only_when_it_wraps() {
+        let level = ".....\n...o>\n.....\n";
+        assert!(!is_safe_move(&level_game(level, WallPolicy::Die), Direction::Right));
+        assert!(!is_safe_move(&level_game(level, WallPolicy::Bounce), Direction::Right));
+        assert!(is_safe_move(&level_game(level, WallPolicy::Wrap), Direction::Right));
+    }
+
+    #[test]
+    fn random_bot_never_picks_an_unsafe_move() {
+        let mut bot = RandomBot::default();
+
+        let corridor = level_game("#######\n#o>...#\n#######\n", WallPolicy::Die);
+        for _ in 0..50 {
+            assert!(matches!(bot.next_input(&corridor), InputType::Nothing | InputType::Right));
+        }
+
+        // nowhere to go, so it doesn't turn into the body or a wall either
+        let dead_end = level_game("######\n#o>#.#\n######\n", WallPolicy::Die);
+        for _ in 0..50 {
+            assert_eq!(bot.next_input(&dead_end), InputType::Nothing);
+        }
+    }
+}
@@ -5,7 +5,7 @@ pub type Coord = Pair<i32>;
 
 impl Coord {
     pub fn new(x: i32, y: i32) -> Coord {
-        Coord { x: x, y: y }
+        Coord { x, y }
     }
 
     pub fn as_tuple(&self) -> (i32, i32) {
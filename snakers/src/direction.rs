@@ -1,12 +1,19 @@
 use crate::coord::Coord;
+use crate::error::SnakersError;
 
 // specific values so we can use as array indices
-#[derive(PartialEq, Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     Up,
     Right,
     Down,
     Left,
+    // only hex boards go this way, see topology.rs
+    UpRight,
+    DownRight,
+    DownLeft,
+    UpLeft,
 }
 
 const ROTATE_UP: ((i32, i32), (i32, i32)) = ((1, 0), (0, 1));
@@ -18,20 +25,39 @@ const UP: Coord = Coord { x: 0, y: -1 };
 const RIGHT: Coord = Coord { x: 1, y: 0 };
 const LEFT: Coord = Coord { x: -1, y: 0 };
 const DOWN: Coord = Coord { x: 0, y: 1 };
+const UP_RIGHT: Coord = Coord { x: 1, y: -1 };
+const DOWN_RIGHT: Coord = Coord { x: 1, y: 1 };
+const DOWN_LEFT: Coord = Coord { x: -1, y: 1 };
+const UP_LEFT: Coord = Coord { x: -1, y: -1 };
 
 impl Direction {
+    // the ways a snake can go on each shape of board, clockwise from up
+    pub const SQUARE: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+    pub const HEX: [Direction; 6] = [
+        Direction::UpRight,
+        Direction::Right,
+        Direction::DownRight,
+        Direction::DownLeft,
+        Direction::Left,
+        Direction::UpLeft,
+    ];
+
+    /*
+     * the diagonals have no quarter turn matrix of their own, so they share the one of the side
+     * they lean away from; only square boards are drawn rotated.
+     * */
     pub fn rotation_matrix(&self) -> &((i32, i32), (i32, i32)) {
         match self {
-            &Direction::Up => &ROTATE_UP,
-            &Direction::Right => &ROTATE_RIGHT,
-            &Direction::Down => &ROTATE_DOWN,
-            &Direction::Left => &ROTATE_LEFT,
+            &Direction::Up | &Direction::UpRight => &ROTATE_UP,
+            &Direction::Right | &Direction::DownRight => &ROTATE_RIGHT,
+            &Direction::Down | &Direction::DownLeft => &ROTATE_DOWN,
+            &Direction::Left | &Direction::UpLeft => &ROTATE_LEFT,
         }
     }
 
     pub fn rotate(&self, p: &Coord) -> Coord {
         let rot = self.rotation_matrix();
-        return Coord {
+        Coord {
             x: (p.x * rot.0.0) + (p.y * rot.0.1),
             y: (p.x * rot.1.0) + (p.y * rot.1.1),
         }
@@ -43,6 +69,10 @@ impl Direction {
             Direction::Right => Direction::Left,
             Direction::Down => Direction::Up,
             Direction::Left => Direction::Right,
+            Direction::UpRight => Direction::DownLeft,
+            Direction::DownRight => Direction::UpLeft,
+            Direction::DownLeft => Direction::UpRight,
+            Direction::UpLeft => Direction::DownRight,
         }
     }
 
@@ -56,17 +86,66 @@ impl Direction {
             Direction::Right => RIGHT,
             Direction::Down => DOWN,
             Direction::Left => LEFT,
+            Direction::UpRight => UP_RIGHT,
+            Direction::DownRight => DOWN_RIGHT,
+            Direction::DownLeft => DOWN_LEFT,
+            Direction::UpLeft => UP_LEFT,
         }
     }
 
-    pub fn direction_from_unit_vector(p: &Coord) -> Direction {
+    pub fn direction_from_unit_vector(p: &Coord) -> Result<Direction, SnakersError> {
+        Direction::try_from(*p)
+    }
+}
+
+impl TryFrom<Coord> for Direction {
+    type Error = SnakersError;
+
+    fn try_from(p: Coord) -> Result<Self, Self::Error> {
         match p {
-            &UP => Direction::Up,
-            &RIGHT => Direction::Right,
-            &LEFT => Direction::Left,
-            &DOWN => Direction::Down,
-            _ => panic!(),
+            UP => Ok(Direction::Up),
+            RIGHT => Ok(Direction::Right),
+            LEFT => Ok(Direction::Left),
+            DOWN => Ok(Direction::Down),
+            UP_RIGHT => Ok(Direction::UpRight),
+            DOWN_RIGHT => Ok(Direction::DownRight),
+            DOWN_LEFT => Ok(Direction::DownLeft),
+            UP_LEFT => Ok(Direction::UpLeft),
+            _ => Err(SnakersError::NotAUnitVector(p)),
         }
     }
 }
 
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Direction::Up => write!(f, "up"),
+            Direction::Right => write!(f, "right"),
+            Direction::Down => write!(f, "down"),
+            Direction::Left => write!(f, "left"),
+            Direction::UpRight => write!(f, "up-right"),
+            Direction::DownRight => write!(f, "down-right"),
+            Direction::DownLeft => write!(f, "down-left"),
+            Direction::UpLeft => write!(f, "up-left"),
+        }
+    }
+}
+
+impl std::str::FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "up" => Ok(Direction::Up),
+            "right" => Ok(Direction::Right),
+            "down" => Ok(Direction::Down),
+            "left" => Ok(Direction::Left),
+            "up-right" => Ok(Direction::UpRight),
+            "down-right" => Ok(Direction::DownRight),
+            "down-left" => Ok(Direction::DownLeft),
+            "up-left" => Ok(Direction::UpLeft),
+            _ => Err(format!("unknown direction '{}'", s)),
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use crate::config::{GameConfig, WallPolicy};
+use crate::coord::Coord;
+use crate::direction::Direction;
+use crate::error::SnakersError;
+use crate::event::GameEvent;
+use crate::game::{GameOutcome, GameState, ItemType, StateTransition};
+use crate::inputtype::Controls;
+use crate::topology::{Shape, Topology};
+
+/*
+ * The game as a reinforcement learning environment: reset it with a seed, then step it one action
+ * at a time and get back what the agent sees, the reward, whether the episode is over and what
+ * happened. Every step is one tick of the real game, so an agent trained here plays by the same
+ * rules as everyone else.
+ *
+ * Actions are numbered in the order Controls::actions gives them. With absolute controls that is
+ * 0 up, 1 right, 2 down, 3 left, or the six hex directions clockwise from up-right on a hex board,
+ * and turning back on itself is ignored, the same as for a player. With relative controls it is
+ * 0 straight on, 1 turn left, 2 turn right.
+ * */
+pub struct Env {
+    options: EnvOptions,
+    game: GameState,
+    done: bool,
+}
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Encoding {
+    // one plane per kind of cell, each as big as the board: CHANNELS x height x width
+    Grid,
+    // the same planes cut down to a square around the head: CHANNELS x side x side, where side is
+    // 2 * radius + 1. off the board counts as wall unless the walls wrap.
+    Window { radius: u32 },
+    // how close the nearest wall, body and food are in each direction a ray goes out from the
+    // head, as 1 / distance (0 when there isn't one), followed by the heading one-hot in the order
+    // of the board's directions
+    Rays,
+}
+
+// the planes of Grid and Window, in order
+pub const CHANNELS: usize = 7;
+const EMPTY: usize = 0;
+const HEAD: usize = 1;
+const BODY: usize = 2;
+const TAIL: usize = 3;
+const FOOD: usize = 4;
+const POWER_UP: usize = 5;
+const WALL: usize = 6;
+
+// the eight directions Rays looks in on a square board, clockwise from up; a hex board looks along
+// its six directions instead
+pub const RAYS: usize = 8;
+const SQUARE_RAYS: [Direction; RAYS] = [
+    Direction::Up,
+    Direction::UpRight,
+    Direction::Right,
+    Direction::DownRight,
+    Direction::Down,
+    Direction::DownLeft,
+    Direction::Left,
+    Direction::UpLeft,
+];
+
+/*
+ * What each step is worth to the agent. The game's own score is there to be used, but the default
+ * is the usual shaping: a point per food, a point off for dying and a lot for clearing the board.
+ * */
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct Reward {
+    // for each food eaten, whatever kind it is
+    pub food: f32,
+    // for each point the game's score goes up by
+    pub score: f32,
+    // every tick, usually a small negative number to hurry the agent along
+    pub tick: f32,
+    // for each step closer to the nearest food, and taken off for each step further away
+    pub approach: f32,
+    pub death: f32,
+    pub win: f32,
+}
+
+impl Default for Reward {
+    fn default() -> Self {
+        Reward {
+            food: 1.0,
+            score: 0.0,
+            tick: 0.0,
+            approach: 0.0,
+            death: -1.0,
+            win: 10.0,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct EnvOptions {
+    pub encoding: Encoding,
+    pub controls: Controls,
+    pub reward: Reward,
+    // episodes are cut short after this many ticks, so an agent that goes round in circles
+    // doesn't run forever
+    pub max_ticks: u64,
+}
+
+impl Default for EnvOptions {
+    fn default() -> Self {
+        EnvOptions {
+            encoding: Encoding::Grid,
+            controls: Controls::Absolute,
+            reward: Reward::default(),
+            max_ticks: 10_000,
+        }
+    }
+}
+
+// everything about a step that isn't the observation, the reward or done
+#[derive(PartialEq, Debug, Clone)]
+pub struct StepInfo {
+    pub tick: u64,
+    pub length: usize,
+    pub score: u64,
+    pub events: Vec<GameEvent>,
+    // how the game ended, when it did
+    pub outcome: Option<GameOutcome>,
+    // the episode hit max_ticks rather than the game ending
+    pub truncated: bool,
+}
+
+impl Env {
+    // starts on seed 0; call reset to pick the seed for the first episode
+    pub fn new(config: GameConfig, options: EnvOptions) -> Result<Self, SnakersError> {
+        let game = GameState::with_seed(config, 0)?;
+        Ok(Env { options, game, done: false })
+    }
+
+    pub fn get_game(&self) -> &GameState {
+        &self.game
+    }
+
+    pub fn get_options(&self) -> &EnvOptions {
+        &self.options
+    }
+
+    pub fn action_count(&self) -> usize {
+        self.options.controls.actions(self.game.get_board().get_shape()).len()
+    }
+
+    // the dimensions of what observe returns, outermost first
+    pub fn observation_shape(&self) -> Vec<usize> {
+        match self.options.encoding {
+            Encoding::Grid => vec![CHANNELS, self.game.get_height() as usize, self.game.get_width() as usize],
+            Encoding::Window { radius } => {
+                let side = 2 * radius as usize + 1;
+                vec![CHANNELS, side, side]
+            }
+            Encoding::Rays => vec![self.rays().len() * 3 + self.game.get_board().directions().len()],
+        }
+    }
+
+    // a new episode on the same config
+    pub fn reset(&mut self, seed: u64) -> Vec<f32> {
+        let config = self.game.get_config().clone();
+        self.game = GameState::with_seed(config, seed).unwrap(); // new checked the config
+        self.done = false;
+        self.observe()
+    }
+
+    // fails only on an action that isn't one of the action_count() there are
+    pub fn step(&mut self, action: usize) -> Result<(Vec<f32>, f32, bool, StepInfo), SnakersError> {
+        if self.done {
+            log::warn!("step called on a finished episode, reset it first");
+            return Ok((self.observe(), 0.0, true, self.info(None, false)));
+        }
+
+        let input = match self.options.controls.actions(self.game.get_board().get_shape()).get(action) {
+            Some(input) => *input,
+            None => return Err(SnakersError::NoSuchAction(action)),
+        };
+
+        let reward = self.options.reward;
+        let score = self.game.get_stats().get_score();
+        let eaten = self.game.get_stats().get_food_eaten();
+        let distance = self.food_distance();
+
+        let outcome = match self.game.handle_input(input) {
+            StateTransition::Stop(outcome) => Some(outcome),
+            StateTransition::Continue => match self.game.update_state() {
+                StateTransition::Stop(outcome) => Some(outcome),
+                StateTransition::Continue => None,
+            },
+        };
+
+        let stats = self.game.get_stats();
+        let mut total = reward.tick
+            + reward.food * (stats.get_food_eaten() - eaten) as f32
+            + reward.score * (stats.get_score() - score) as f32;
+
+        // only counts when there was food to get closer to both before and after
+        if let (Some(before), Some(after)) = (distance, self.food_distance()) {
+            if stats.get_food_eaten() == eaten {
+                total += reward.approach * (before as f32 - after as f32);
+            }
+        }
+
+        match outcome {
+            Some(outcome) if outcome.cause.is_win() => total += reward.win,
+            Some(_) => total += reward.death,
+            None => (),
+        }
+
+        let truncated = outcome.is_none() && self.game.get_tick() >= self.options.max_ticks;
+        self.done = outcome.is_some() || truncated;
+
+        Ok((self.observe(), total, self.done, self.info(outcome, truncated)))
+    }
+
+    fn info(&self, outcome: Option<GameOutcome>, truncated: bool) -> StepInfo {
+        StepInfo {
+            tick: self.game.get_tick(),
+            length: self.game.get_snake().get_body().len(),
+            score: self.game.get_stats().get_score(),
+            events: self.game.get_events().to_vec(),
+            outcome,
+            truncated,
+        }
+    }
+
+    // the game as it is now, in the encoding from the options
+    pub fn observe(&self) -> Vec<f32> {
+        match self.options.encoding {
+            Encoding::Grid => self.observe_grid(),
+            Encoding::Window { radius } => self.observe_window(radius as i32),
+            Encoding::Rays => self.observe_rays(),
+        }
+    }
+
+    fn observe_grid(&self) -> Vec<f32> {
+        let (width, height) = (self.game.get_width() as usize, self.game.get_height() as usize);
+        let mut planes = vec![0.0; CHANNELS * height * width];
+
+        for (y, row) in self.game.get_world().iter().enumerate() {
+            for (x, item) in row.iter().enumerate() {
+                planes[(channel(item) * height + y) * width + x] = 1.0;
+            }
+        }
+
+        planes
+    }
+
+    fn observe_window(&self, radius: i32) -> Vec<f32> {
+        let head = self.head();
+        let side = (2 * radius + 1) as usize;
+        let mut planes = vec![0.0; CHANNELS * side * side];
+
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let item = self.look(&Coord::new(head.x + dx, head.y + dy));
+                let (x, y) = ((dx + radius) as usize, (dy + radius) as usize);
+                planes[(channel(&item) * side + y) * side + x] = 1.0;
+            }
+        }
+
+        planes
+    }
+
+    fn rays(&self) -> &'static [Direction] {
+        match self.game.get_board().get_shape() {
+            Shape::Square => &SQUARE_RAYS,
+            Shape::Hex => &Direction::HEX,
+        }
+    }
+
+    fn observe_rays(&self) -> Vec<f32> {
+        let board = self.game.get_board();
+        let head = self.head();
+        // far enough to go all the way across the board, or all the way round if it wraps
+        let reach = self.game.get_width().max(self.game.get_height()) as i32;
+        let mut rays = Vec::with_capacity(self.rays().len() * 3 + board.directions().len());
+
+        for &ray in self.rays() {
+            let (mut wall, mut body, mut food) = (0.0, 0.0, 0.0);
+            let mut at = head;
+
+            // a neighbor at a time, since hex rows are offset; a square board goes diagonally too
+            for distance in 1..=reach {
+                at = board.neighbor(&at, ray);
+                let nearness = 1.0 / distance as f32;
+                match self.look(&at) {
+                    ItemType::Wall => {
+                        wall = nearness;
+                        break;
+                    }
+                    ItemType::SnakeBit | ItemType::SnakeTail | ItemType::SnakeHead if body == 0.0 => body = nearness,
+                    ItemType::Food(_) if food == 0.0 => food = nearness,
+                    _ => (),
+                }
+            }
+
+            rays.extend([wall, body, food]);
+        }
+
+        let heading = self.game.get_heading();
+        rays.extend(board.directions().iter().map(|d| if *d == heading { 1.0 } else { 0.0 }));
+
+        rays
+    }
+
+    fn head(&self) -> Coord {
+        self.game.get_snake().get_body().front().unwrap().coord // all snakes have a head
+    }
+
+    // what's at a cell as the snake would find it, so the edge is a wall unless the walls wrap
+    fn look(&self, at: &Coord) -> ItemType {
+        if self.game.in_bounds(at) {
+            return self.game[at];
+        }
+        match self.game.get_wall_policy() {
+            WallPolicy::Wrap => self.game[&self.game.wrap(at)],
+            WallPolicy::Die | WallPolicy::Bounce => ItemType::Wall,
+        }
+    }
+
+    // steps from the head to the nearest food, ignoring anything in the way but going across the
+    // edges if they wrap
+    fn food_distance(&self) -> Option<u32> {
+        let head = self.head();
+        let wrap = self.game.get_wall_policy() == WallPolicy::Wrap;
+        let mut nearest = None;
+        for (y, row) in self.game.get_world().iter().enumerate() {
+            for (x, item) in row.iter().enumerate() {
+                if let ItemType::Food(_) = item {
+                    let at = self.game.grid_to_game(&Coord::new(x as i32, y as i32));
+                    let distance = self.game.get_board().distance(&head, &at, wrap);
+                    nearest = Some(nearest.map_or(distance, |n: u32| n.min(distance)));
+                }
+            }
+        }
+        nearest
+    }
+}
+
+fn channel(item: &ItemType) -> usize {
+    match item {
+        ItemType::Nothing => EMPTY,
+        ItemType::SnakeHead => HEAD,
+        ItemType::SnakeBit => BODY,
+        ItemType::SnakeTail => TAIL,
+        ItemType::Food(_) => FOOD,
+        ItemType::PowerUp(_) => POWER_UP,
+        ItemType::Wall => WALL,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env() -> Env {
+        let config = GameConfig::builder().size(10, 8).wall_policy(WallPolicy::Die).build().unwrap();
+        Env::new(config, EnvOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn no_such_action() {
+        let mut env = env();
+        assert_eq!(env.action_count(), 4);
+        assert_eq!(env.step(4).err(), Some(SnakersError::NoSuchAction(4)));
+        assert_eq!(env.get_game().get_tick(), 0);
+
+        // up until it hits the wall, after which any action is ignored
+        while !env.step(0).unwrap().2 {}
+        let (_, reward, done, _) = env.step(99).unwrap();
+        assert_eq!((reward, done), (0.0, true));
+    }
+
+    #[test]
+    fn food_distance_wraps() {
+        let config = GameConfig::builder().size(10, 8).wall_policy(WallPolicy::Wrap).build().unwrap();
+        let mut env = Env::new(config, EnvOptions::default()).unwrap();
+
+        // across the edges nothing on a 10x8 board is more than 5 + 4 steps away
+        for seed in 0..50 {
+            env.reset(seed);
+            assert!(env.food_distance().unwrap() <= 9, "seed {}", seed);
+        }
+    }
+}
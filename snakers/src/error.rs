@@ -0,0 +1,58 @@
+use crate::config::ConfigError;
+use crate::coord::Coord;
+use crate::level::LevelError;
+use crate::replay::ReplayError;
+
+/*
+ * Anything the core can refuse to do. The more specific errors (ConfigError, LevelError,
+ * ReplayError) convert into this, so an embedding application can use one error type and `?`
+ * throughout.
+ * */
+#[derive(PartialEq, Debug, Clone)]
+pub enum SnakersError {
+    Config(ConfigError),
+    Level(LevelError),
+    Replay(ReplayError),
+    // a coordinate that is not on the board
+    OutOfBounds(Coord),
+    // a coordinate that was meant to be one step up, right, down or left
+    NotAUnitVector(Coord),
+    // an action number past the end of what the controls have
+    NoSuchAction(usize),
+    // a game state that can't have come from playing, like a hand edited snapshot
+    BadState(String),
+}
+
+impl std::fmt::Display for SnakersError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SnakersError::Config(e) => write!(f, "bad config: {}", e),
+            SnakersError::Level(e) => write!(f, "bad level: {}", e),
+            SnakersError::Replay(e) => write!(f, "bad replay: {}", e),
+            SnakersError::OutOfBounds(at) => write!(f, "{} is off the board", at),
+            SnakersError::NotAUnitVector(v) => write!(f, "{} is not a unit vector", v),
+            SnakersError::NoSuchAction(a) => write!(f, "there is no action {}", a),
+            SnakersError::BadState(why) => write!(f, "bad game state: {}", why),
+        }
+    }
+}
+
+impl std::error::Error for SnakersError {}
+
+impl From<ConfigError> for SnakersError {
+    fn from(e: ConfigError) -> Self {
+        SnakersError::Config(e)
+    }
+}
+
+impl From<LevelError> for SnakersError {
+    fn from(e: LevelError) -> Self {
+        SnakersError::Level(e)
+    }
+}
+
+impl From<ReplayError> for SnakersError {
+    fn from(e: ReplayError) -> Self {
+        SnakersError::Replay(e)
+    }
+}
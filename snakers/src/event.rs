@@ -0,0 +1,45 @@
+use crate::coord::Coord;
+use crate::direction::Direction;
+use crate::food::FoodKind;
+use crate::game::GameOverCause;
+use crate::powerup::PowerUp;
+
+/*
+ * Something that happened during a tick, so a frontend can react (flash a message, play a sound)
+ * without diffing the grid. GameState::get_events has the ones from the last update_state, in the
+ * order they happened. Coordinates are game coordinates.
+ * */
+#[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameEvent {
+    FoodEaten(Coord, FoodKind),
+    FoodSpawned(Coord, FoodKind),
+    // the snake ended the tick longer, or shorter, than it started it
+    Grew,
+    Shrank,
+    // the head moved a different way than on the tick before
+    DirectionChanged(Direction),
+    PowerUpSpawned(Coord, PowerUp),
+    PowerUpCollected(Coord, PowerUp),
+    // a power-up's effect ran out
+    EffectEnded(PowerUp),
+    Died(GameOverCause),
+    Won,
+}
+
+impl std::fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GameEvent::FoodEaten(at, kind) => write!(f, "ate {} food at {}", kind, at),
+            GameEvent::FoodSpawned(at, kind) => write!(f, "{} food appeared at {}", kind, at),
+            GameEvent::Grew => write!(f, "grew"),
+            GameEvent::Shrank => write!(f, "shrank"),
+            GameEvent::DirectionChanged(d) => write!(f, "turned {}", d),
+            GameEvent::PowerUpSpawned(at, kind) => write!(f, "{} power-up appeared at {}", kind, at),
+            GameEvent::PowerUpCollected(_, kind) => write!(f, "{} on", kind),
+            GameEvent::EffectEnded(kind) => write!(f, "{} off", kind),
+            GameEvent::Died(cause) => write!(f, "died: {}", cause),
+            GameEvent::Won => write!(f, "won"),
+        }
+    }
+}
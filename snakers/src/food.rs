@@ -0,0 +1,186 @@
+use rand::Rng;
+
+/*
+ * Food comes in kinds, each with its own rule for how likely it is to be the next food dropped,
+ * how much it changes the snake's length and how much it is worth. Regular food is the one the
+ * game always had; the others are there to mix things up and can be switched off by giving them
+ * no weight.
+ * */
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FoodKind {
+    Regular,
+    Big,
+    // makes the snake shorter
+    Shrink,
+    // worth a lot, doesn't grow the snake much
+    Golden,
+}
+
+impl FoodKind {
+    pub const ALL: [FoodKind; 4] = [FoodKind::Regular, FoodKind::Big, FoodKind::Shrink, FoodKind::Golden];
+
+    fn index(&self) -> usize {
+        match self {
+            FoodKind::Regular => 0,
+            FoodKind::Big => 1,
+            FoodKind::Shrink => 2,
+            FoodKind::Golden => 3,
+        }
+    }
+}
+
+impl std::fmt::Display for FoodKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FoodKind::Regular => write!(f, "regular"),
+            FoodKind::Big => write!(f, "big"),
+            FoodKind::Shrink => write!(f, "shrink"),
+            FoodKind::Golden => write!(f, "golden"),
+        }
+    }
+}
+
+impl std::str::FromStr for FoodKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "regular" => Ok(FoodKind::Regular),
+            "big" => Ok(FoodKind::Big),
+            "shrink" => Ok(FoodKind::Shrink),
+            "golden" => Ok(FoodKind::Golden),
+            _ => Err(format!("unknown food kind '{}'", s)),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FoodRule {
+    // relative chance of being picked when food is dropped, 0 means never
+    pub weight: u32,
+    // added to how much the snake still has to grow; negative shrinks it
+    pub growth: i32,
+    // counts towards the score, ScoreFormula::per_food points each
+    pub value: u64,
+}
+
+// a FoodRule for every FoodKind
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FoodTable {
+    rules: [FoodRule; 4],
+}
+
+impl FoodTable {
+    pub fn get(&self, kind: FoodKind) -> FoodRule {
+        self.rules[kind.index()]
+    }
+
+    pub fn set(&mut self, kind: FoodKind, rule: FoodRule) {
+        self.rules[kind.index()] = rule;
+    }
+
+    pub fn total_weight(&self) -> u64 {
+        self.rules.iter().map(|rule| rule.weight as u64).sum()
+    }
+
+    // a kind picked by weight, None when every weight is 0
+    pub(crate) fn pick<R: Rng>(&self, rng: &mut R) -> Option<FoodKind> {
+        let total = self.total_weight();
+        if total == 0 {
+            return None;
+        }
+
+        let mut n = rng.gen_range(0..total);
+        for kind in FoodKind::ALL {
+            let weight = self.get(kind).weight as u64;
+            if n < weight {
+                return Some(kind);
+            }
+            n -= weight;
+        }
+        unreachable!()
+    }
+}
+
+impl Default for FoodTable {
+    fn default() -> Self {
+        FoodTable {
+            rules: [
+                FoodRule { weight: 80, growth: 3, value: 1 },
+                FoodRule { weight: 10, growth: 6, value: 3 },
+                FoodRule { weight: 5, growth: -3, value: 0 },
+                FoodRule { weight: 5, growth: 1, value: 5 },
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::AStarBot;
+    use crate::config::{GameConfig, WallPolicy};
+    use crate::controller::Controller;
+    use crate::game::GameState;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    // a table with only this food in it
+    fn only(kind: FoodKind, rule: FoodRule) -> FoodTable {
+        let mut table = FoodTable::default();
+        for other in FoodKind::ALL {
+            table.set(other, FoodRule { weight: 0, ..table.get(other) });
+        }
+        table.set(kind, rule);
+        table
+    }
+
+    #[test]
+    fn picked_by_weight() {
+        let mut table = only(FoodKind::Regular, FoodRule { weight: 3, growth: 1, value: 1 });
+        table.set(FoodKind::Golden, FoodRule { weight: 1, growth: 1, value: 5 });
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+
+        let mut counts = [0; 4];
+        for _ in 0..4000 {
+            counts[table.pick(&mut rng).unwrap().index()] += 1;
+        }
+        assert_eq!((counts[1], counts[2]), (0, 0));
+        assert!((2800..3200).contains(&counts[0]), "{:?}", counts);
+        assert_eq!(counts[0] + counts[3], 4000);
+
+        let none = only(FoodKind::Regular, FoodRule { weight: 0, growth: 1, value: 1 });
+        assert_eq!(none.total_weight(), 0);
+        assert_eq!(none.pick(&mut rng), None);
+    }
+
+    // the snake's length once it has eaten one of the food and finished growing or shrinking
+    fn after_one(kind: FoodKind, rule: FoodRule, initial_length: i32) -> (usize, u64) {
+        let mut builder = GameConfig::builder()
+            .size(16, 12)
+            .wall_policy(WallPolicy::Wrap)
+            .initial_length(initial_length);
+        for k in FoodKind::ALL {
+            builder = builder.food(k, only(kind, rule).get(k));
+        }
+        let mut game = GameState::with_seed(builder.build().unwrap(), 4).unwrap();
+        let mut bot = AStarBot::default();
+        while game.get_stats().get_food_eaten() == 0 || game.get_snake().get_growing() != 0 {
+            game.handle_input(bot.next_input(&game));
+            game.update_state();
+        }
+        assert_eq!(game.get_stats().get_food_eaten(), 1);
+        (game.get_snake().get_body().len(), game.get_stats().get_score())
+    }
+
+    #[test]
+    fn growth_and_value_by_kind() {
+        assert_eq!(after_one(FoodKind::Big, FoodRule { weight: 1, growth: 4, value: 3 }, 5), (9, 30));
+        assert_eq!(after_one(FoodKind::Shrink, FoodRule { weight: 1, growth: -2, value: 0 }, 5), (3, 0));
+        // but never shorter than a head and a tail
+        assert_eq!(after_one(FoodKind::Shrink, FoodRule { weight: 1, growth: -5, value: 0 }, 3), (2, 0));
+    }
+}
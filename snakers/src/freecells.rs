@@ -0,0 +1,147 @@
+use crate::coord::Coord;
+
+/*
+ * The set of empty cells on the board, so food can be placed by picking uniformly from what is
+ * actually free instead of guessing. Cells are kept in a Vec for O(1) random picks, with a
+ * per-cell slot index so adding and removing are O(1) too (removal swaps the last cell into the
+ * hole). Coordinates here are grid coordinates, (0, 0) being the top left.
+ *
+ * Only the cells are saved. The slot index is built again from them on loading, so a saved game
+ * can't bring an index that points somewhere it shouldn't.
+ * */
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "SavedFreeCells"))]
+pub(crate) struct FreeCells {
+    width: usize,
+    height: usize,
+    cells: Vec<Coord>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    slots: Vec<Option<usize>>,
+}
+
+// what goes in a snapshot
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SavedFreeCells {
+    width: usize,
+    height: usize,
+    cells: Vec<Coord>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SavedFreeCells> for FreeCells {
+    type Error = String;
+
+    fn try_from(saved: SavedFreeCells) -> Result<Self, Self::Error> {
+        let size = saved.width.checked_mul(saved.height).ok_or("free cells board is too big")?;
+        let mut free = FreeCells {
+            width: saved.width,
+            height: saved.height,
+            cells: Vec::with_capacity(saved.cells.len()),
+            slots: vec![None; size],
+        };
+
+        for at in saved.cells {
+            if !free.on_board(&at) {
+                return Err(format!("free cell {} is off the board", at));
+            }
+            if free.slots[free.slot(&at)].is_some() {
+                return Err(format!("free cell {} is in the list twice", at));
+            }
+            free.insert(&at);
+        }
+
+        Ok(free)
+    }
+}
+
+impl FreeCells {
+    // starts out with every cell free
+    pub(crate) fn new(width: u32, height: u32) -> Self {
+        // in usize, as the u32 product can overflow on a big enough board
+        let size = width as usize * height as usize;
+        let mut free = FreeCells {
+            width: width as usize,
+            height: height as usize,
+            cells: Vec::with_capacity(size),
+            slots: vec![None; size],
+        };
+
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                free.insert(&Coord::new(x, y));
+            }
+        }
+
+        free
+    }
+
+    fn slot(&self, at: &Coord) -> usize {
+        (at.y as usize * self.width) + at.x as usize
+    }
+
+    fn on_board(&self, at: &Coord) -> bool {
+        at.x >= 0 && at.y >= 0 && (at.x as usize) < self.width && (at.y as usize) < self.height
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub(crate) fn get(&self, n: usize) -> Coord {
+        self.cells[n]
+    }
+
+    pub(crate) fn iter(&self) -> std::slice::Iter<'_, Coord> {
+        self.cells.iter()
+    }
+
+    // whether this is for a board this size, and every cell is on it with a slot that points back to it
+    pub(crate) fn is_indexed(&self, width: u32, height: u32) -> bool {
+        if (self.width, self.height) != (width as usize, height as usize)
+            || self.slots.len() != self.width * self.height
+        {
+            return false;
+        }
+
+        let pointing_back = self.cells.iter().enumerate()
+            .all(|(n, at)| self.on_board(at) && self.slots[self.slot(at)] == Some(n));
+        pointing_back && self.slots.iter().flatten().count() == self.cells.len()
+    }
+
+    pub(crate) fn insert(&mut self, at: &Coord) {
+        let slot = self.slot(at);
+        if self.slots[slot].is_none() {
+            self.slots[slot] = Some(self.cells.len());
+            self.cells.push(*at);
+        }
+    }
+
+    // returns where the cell was, if it was free, for put_back
+    pub(crate) fn remove(&mut self, at: &Coord) -> Option<usize> {
+        let slot = self.slot(at);
+        let n = self.slots[slot].take()?;
+        self.cells.swap_remove(n);
+        if n < self.cells.len() {
+            let moved = self.slot(&self.cells[n]);
+            self.slots[moved] = Some(n);
+        }
+        Some(n)
+    }
+
+    // takes back a remove, so the cells are in the same order as before and the same pick finds the same cell
+    pub(crate) fn put_back(&mut self, n: usize, at: &Coord) {
+        if n < self.cells.len() {
+            let moved = self.cells[n];
+            let moved_slot = self.slot(&moved);
+            self.slots[moved_slot] = Some(self.cells.len());
+            self.cells.push(moved);
+            self.cells[n] = *at;
+        } else {
+            self.cells.push(*at);
+        }
+        let slot = self.slot(at);
+        self.slots[slot] = Some(n);
+    }
+}
@@ -1,24 +1,49 @@
-use std::collections::LinkedList;
+use std::collections::VecDeque;
+
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
 
 use rand::Rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 
+use crate::body::Body;
+use crate::config::{GameConfig, WallPolicy, MINIMUM_SNAKE_LENGTH};
 use crate::direction::Direction;
+use crate::error::SnakersError;
+use crate::event::GameEvent;
+use crate::food::FoodKind;
 use crate::inputtype::InputType;
 use crate::coord::Coord;
-
-const INITIAL_SNAKE_LENGTH: i32 = 7;
-const SNAKE_GROWTH_PER_FOOD: i32 = 3;
-
-#[derive(Debug, PartialEq)]
+use crate::freecells::FreeCells;
+use crate::history::{Delta, History};
+use crate::hash::StateHasher;
+use crate::level::Level;
+use crate::powerup::{ActiveEffect, PowerUp};
+use crate::topology::{Board, Topology};
+use crate::stats::GameStats;
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ItemType {
     Nothing,
     SnakeHead,
     SnakeBit,
     SnakeTail,
-    Food,
+    Food(FoodKind),
+    PowerUp(PowerUp),
+    // terrain, fixed for the whole game
+    Wall,
+}
+
+impl ItemType {
+    pub fn is_terrain(&self) -> bool {
+        matches!(self, ItemType::Wall)
+    }
 }
 
-#[derive(Copy,Clone,Debug)]
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CoordWithDirection {
     pub dir_next: Direction,
     pub coord: Coord,
@@ -31,23 +56,40 @@ impl CoordWithDirection {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SnakeType {
-    body: LinkedList<CoordWithDirection>,
+    body: Body,
     growing: i32,
+    // power-up effects running now, in the order they were picked up
+    effects: Vec<ActiveEffect>,
 }
 
 impl SnakeType {
-    pub fn get_body(&self) -> &LinkedList<CoordWithDirection> {
+    pub fn get_body(&self) -> &Body {
         &(self.body)
     }
     pub fn get_growing(&self) -> i32 {
         self.growing
     }
+    pub fn get_effects(&self) -> &[ActiveEffect] {
+        &self.effects
+    }
+    pub fn has_effect(&self, kind: PowerUp) -> bool {
+        self.effects.iter().any(|e| e.kind == kind)
+    }
 }
 
 pub type GridType = Vec<Vec<ItemType>>;
 
+// a portable, seedable generator so the same seed gives the same game on every platform
+pub type GameRng = ChaCha8Rng;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GameState {
+    config: GameConfig,
+
     // grid size
     width: u32,
     height: u32,
@@ -55,11 +97,33 @@ pub struct GameState {
     world: GridType,
     snake: SnakeType,
 
-    // logical game state bounds
-    xrange: (i32, i32),
-    yrange: (i32, i32),
+    // every cell that is ItemType::Nothing, in grid coordinates
+    free: FreeCells,
+
+    // where the power-up waiting to be picked up is, there is at most one
+    power_up: Option<Coord>,
 
-    pending_input: InputType,
+    // logical game state bounds, and how the cells connect
+    board: Board,
+
+    // turns waiting for the next ticks, oldest first
+    turns: VecDeque<Direction>,
+
+    // number of update_state calls so far
+    tick: u64,
+
+    stats: GameStats,
+
+    // what happened on the last update_state (or while setting up, before the first)
+    events: Vec<GameEvent>,
+
+    // everything random in the game comes from here so a seed reproduces a whole game
+    seed: u64,
+    rng: GameRng,
+
+    // the last few ticks, so they can be taken back. a saved game starts a new one when it's loaded
+    #[cfg_attr(feature = "serde", serde(skip))]
+    history: History,
 }
 
 fn init_grid(width: u32, height: u32) -> Vec<Vec<ItemType>> {
@@ -71,47 +135,115 @@ fn init_grid(width: u32, height: u32) -> Vec<Vec<ItemType>> {
         }
         row_vec.push(row);
     }
-    return row_vec;
+    row_vec
 }
 
-pub enum StateTransition {
-    Continue,
-    Stop,
+#[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameOverCause {
+    HitWall,
+    HitSelf,
+    Quit,
+    BoardCleared,
 }
 
-fn make_coordinate_range(size: u32) -> (i32, i32) {
-    match size % 2 {
-        0 => {
-            let half = (size / 2) as i32;
-            return (-half, half - 1);
-        }
-        _ => {
-            let half = ((size - 1) / 2) as i32;
-            return (-half, half);
+impl GameOverCause {
+    pub fn is_win(&self) -> bool {
+        *self == GameOverCause::BoardCleared
+    }
+}
+
+impl std::fmt::Display for GameOverCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GameOverCause::HitWall => write!(f, "hit the wall"),
+            GameOverCause::HitSelf => write!(f, "ran into itself"),
+            GameOverCause::Quit => write!(f, "quit"),
+            GameOverCause::BoardCleared => write!(f, "cleared the board"),
         }
     }
 }
 
+// how a game ended. `at` is the cell the head was moving into, which can be off the board.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct GameOutcome {
+    pub cause: GameOverCause,
+    pub tick: u64,
+    pub at: Coord,
+    pub length: usize,
+    pub score: u64,
+}
+
+impl std::fmt::Display for GameOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "game over: {} at {} on tick {}; length {}, score {}",
+            self.cause, self.at, self.tick, self.length, self.score
+        )
+    }
+}
+
+pub enum StateTransition {
+    Continue,
+    Stop(GameOutcome),
+}
+
 impl GameState {
-    pub fn new(width: u32, height: u32) -> Self {
+    pub fn new(config: GameConfig) -> Result<Self, SnakersError> {
+        Self::with_seed(config, rand::thread_rng().gen())
+    }
+
+    pub fn with_seed(config: GameConfig, seed: u64) -> Result<Self, SnakersError> {
+        config.validate()?;
+        Ok(Self::with_rng(config, seed, GameRng::seed_from_u64(seed)))
+    }
+
+    fn with_rng(config: GameConfig, seed: u64, rng: GameRng) -> Self {
+        let width = config.get_width();
+        let height = config.get_height();
+        let initial_length = config.get_initial_length();
+        let board = config.get_board();
+
         let mut state = GameState {
-            width: width,
-            height: height,
+            config,
+
+            width,
+            height,
 
             world: init_grid(width, height),
 
+            free: FreeCells::new(width, height),
+
             snake: SnakeType {
                 // direction: Direction::Up,
-                body: LinkedList::new(),
+                body: Body::new(&board),
                 growing: 0,
+                effects: Vec::new(),
             },
 
-            xrange: make_coordinate_range(width),
-            yrange: make_coordinate_range(height),
+            power_up: None,
+
+            board,
+
+            turns: VecDeque::new(),
+
+            tick: 0,
+
+            stats: GameStats::new(initial_length as usize),
 
-            pending_input: InputType::Nothing,
+            events: Vec::new(),
+
+            seed,
+            rng,
+
+            history: History::default(),
         };
 
+        for wall in state.config.get_walls().to_vec() {
+            state.set_item(&wall, ItemType::Wall);
+        }
+
         state.initialize_snake();
 
         state.drop_new_food();
@@ -135,67 +267,290 @@ impl GameState {
         self.height
     }
 
-    pub fn handle_input(&mut self, input: InputType) -> StateTransition {
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
 
-        match input {
-            InputType::Quit => StateTransition::Stop,
-            InputType::Nothing => StateTransition::Continue,
-            _ => {
+    pub fn get_config(&self) -> &GameConfig {
+        &self.config
+    }
 
-                // TODO does this count as handling the input twice?
-                self.pending_input = input;
+    pub fn get_stats(&self) -> &GameStats {
+        &self.stats
+    }
 
-                // update head next_dir if it is not trying to go back on itself which is "not
-                // supported"
+    pub fn get_tick(&self) -> u64 {
+        self.tick
+    }
 
-                let head = self.snake.body.front_mut().unwrap(); // all snakes have a head
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn get_events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    // how long the next tick should take, from the config's speed curve and slow motion
+    pub fn tick_interval(&self) -> Duration {
+        let interval = self.config.get_speed().interval(&self.stats);
+        match self.snake.has_effect(PowerUp::SlowMotion) {
+            true => interval * 2,
+            false => interval,
+        }
+    }
 
-                head.dir_next = match input.get_direction() {
-                    Some(d) => {
-                        if d != head.dir_prev {
-                            d
-                        } else {
-                            head.dir_next
-                        }
-                    },
-                    None => head.dir_next,
-                };
+    /*
+     * a fingerprint of everything that changes while playing (the config is fixed for the game,
+     * so it is left out). two games with the same hash are, for all practical purposes, in the
+     * same state; a replay uses this to check it ended up where the recording did.
+     * */
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        self.tick.hash(&mut hasher);
+        self.world.hash(&mut hasher);
+        self.snake.body.hash(&mut hasher);
+        self.snake.growing.hash(&mut hasher);
+        self.snake.effects.hash(&mut hasher);
+        self.turns.hash(&mut hasher);
+        self.stats.hash(&mut hasher);
+        self.seed.hash(&mut hasher);
+        self.rng.get_word_pos().hash(&mut hasher);
+        hasher.finish()
+    }
 
-                return StateTransition::Continue;
+    pub fn handle_input(&mut self, input: InputType) -> StateTransition {
+        // relative turns go from wherever the turns already queued leave the snake heading
+        let input = input.resolve(self.get_queued_heading(), &self.board);
+        match input {
+            InputType::Quit => StateTransition::Stop(self.quit_outcome()),
+            _ => {
+                if let Some(direction) = input.get_direction() {
+                    self.queue_turn(direction);
+                }
+                StateTransition::Continue
             },
         }
     }
 
     pub fn update_state(&mut self) -> StateTransition {
-        // apply the held keypress
-        match self.process_input() {
-            StateTransition::Continue => self.try_move_snake(),
-            s => s,
+        // a new tick is a different future from the one that was rewound
+        self.history.clear_redo();
+        self.step()
+    }
+
+    fn step(&mut self) -> StateTransition {
+        let head = *self.snake.body.front().unwrap(); // all snakes have a head
+        if let Some(delta) = self.history.begin(head) {
+            delta.tick = self.tick;
+            delta.growing = self.snake.growing;
+            delta.effects.clone_from(&self.snake.effects);
+            delta.power_up = self.power_up;
+            delta.turns.clone_from(&self.turns);
+            delta.stats = self.stats;
+            delta.word_pos = self.rng.get_word_pos();
+            // the last tick's events are kept, and this one's go in the delta's old buffer
+            std::mem::swap(&mut delta.events, &mut self.events);
         }
+
+        self.tick += 1;
+        self.events.clear();
+
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("tick", tick = self.tick).entered();
+
+        self.take_turn();
+        self.count_down_effects();
+
+        let transition = self.try_move_snake();
+        if let StateTransition::Stop(outcome) = &transition {
+            log::info!("{}", outcome);
+            self.events.push(match outcome.cause {
+                GameOverCause::BoardCleared => GameEvent::Won,
+                cause => GameEvent::Died(cause),
+            });
+        }
+
+        self.history.commit();
+        transition
     }
 
-    fn process_input(&mut self) -> StateTransition {
-        match self.pending_input {
-            input @ InputType::Up
-            | input @ InputType::Right
-            | input @ InputType::Down
-            | input @ InputType::Left => {
-                self.pending_input = InputType::Nothing;
-                return self.handle_direction(input.get_direction().unwrap())
-            },
-            InputType::Quit => StateTransition::Stop,
-            _ => StateTransition::Continue,
+    /*
+     * takes back up to n ticks, as many as the history has, and returns how many it took back.
+     * the game goes back exactly as it was before them, the RNG too, so playing on with the same
+     * inputs plays the same ticks again. a game over can be taken back like any other tick.
+     * */
+    pub fn rewind(&mut self, n: usize) -> usize {
+        for done in 0..n {
+            match self.history.pop_undo() {
+                Some(delta) => self.undo(delta),
+                None => return done,
+            }
+        }
+        n
+    }
+
+    /*
+     * plays again up to n of the ticks just taken back, with the turns that were waiting for them
+     * then (any queued since are dropped), and returns how many it played. the first
+     * update_state after a rewind drops the rest.
+     * */
+    pub fn redo(&mut self, n: usize) -> usize {
+        for done in 0..n {
+            match self.history.pop_redo() {
+                Some(turns) => {
+                    self.turns = turns;
+                    self.step();
+                }
+                None => return done,
+            }
+        }
+        n
+    }
+
+    // how many ticks rewind and redo could go
+    pub fn get_rewindable(&self) -> usize {
+        self.history.undo_len()
+    }
+
+    pub fn get_redoable(&self) -> usize {
+        self.history.redo_len()
+    }
+
+    pub fn get_history_depth(&self) -> usize {
+        self.history.get_depth()
+    }
+
+    // how many ticks can be taken back, 0 to keep none
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.history.set_depth(depth);
+    }
+
+    // everything is put back in the reverse of the order the tick did it
+    fn undo(&mut self, delta: Delta) {
+        for (g, item, free_at) in delta.cells.into_iter().rev() {
+            let cell = &mut self.world[g.y as usize][g.x as usize];
+            match free_at {
+                // it was free, so it goes back to where it was among the free cells
+                Some(n) => self.free.put_back(n, &g),
+                // it was freed, and went on the end
+                None if *cell == ItemType::Nothing && item != ItemType::Nothing => {
+                    self.free.remove(&g);
+                },
+                None => (),
+            }
+            *cell = item;
+        }
+
+        for tail in delta.tails.into_iter().rev() {
+            self.snake.body.push_back(tail);
+        }
+        if let Some(replaced) = delta.moved {
+            self.snake.body.pop_front(replaced);
+        }
+        *self.snake.body.front_mut().unwrap() = delta.head;
+
+        self.history.push_redo(delta.turns.clone());
+
+        self.tick = delta.tick;
+        self.snake.growing = delta.growing;
+        self.snake.effects = delta.effects;
+        self.power_up = delta.power_up;
+        self.turns = delta.turns;
+        self.stats = delta.stats;
+        self.events = delta.events;
+        self.rng.set_word_pos(delta.word_pos);
+    }
+
+    // the way the head is going, before any queued turns
+    pub fn get_heading(&self) -> Direction {
+        self.snake.body.front().unwrap().dir_next // all snakes have a head
+    }
+
+    // the way the snake will be going once the queued turns are taken
+    pub fn get_queued_heading(&self) -> Direction {
+        match self.turns.back() {
+            Some(turn) => *turn,
+            None => self.get_heading(),
+        }
+    }
+
+    /*
+     * a turn is checked against the way the snake will be going when it comes off the queue: the
+     * last queued turn, or the current heading when nothing is queued. going back on itself is
+     * "not supported" and carrying on straight is not a turn, so both are dropped instead of using
+     * up a tick, as is anything past the queue depth, or a way this board doesn't go.
+     * */
+    fn queue_turn(&mut self, direction: Direction) {
+        let heading = self.get_queued_heading();
+
+        if !self.board.has_direction(direction) {
+            log::debug!("not turning {:?} on a {} board", direction, self.board.get_shape());
+            return;
+        }
+
+        if direction == heading || direction == heading.get_disallowed() {
+            log::debug!("not turning {:?} when heading {:?}", direction, heading);
+            return;
+        }
+
+        if self.turns.len() < self.config.get_turn_queue_depth() {
+            self.turns.push_back(direction);
+        } else {
+            log::debug!("turn queue is full, dropping {:?}", direction);
+        }
+    }
+
+    // one queued turn per tick
+    fn take_turn(&mut self) {
+        if let Some(direction) = self.turns.pop_front() {
+            let head = self.snake.body.front_mut().unwrap();
+            // a bounce off the edge can change the heading after the turn was queued
+            if direction != head.dir_next.get_disallowed() {
+                log::debug!("changing direction from {:?} to {:?}", head.dir_next, direction);
+                head.dir_next = direction;
+            } else {
+                log::debug!("not changing direction from {:?} to {:?}", head.dir_next, direction);
+            }
+        }
+    }
+
+    // effects count down at the start of each tick and are gone once they get to 0
+    fn count_down_effects(&mut self) {
+        for effect in self.snake.effects.iter_mut() {
+            effect.ticks_left = effect.ticks_left.saturating_sub(1);
+        }
+
+        let events = &mut self.events;
+        self.snake.effects.retain(|effect| {
+            if effect.ticks_left == 0 {
+                events.push(GameEvent::EffectEnded(effect.kind));
+            }
+            effect.ticks_left > 0
+        });
+    }
+
+    // picking up a power-up that is already running starts it over
+    fn start_effect(&mut self, kind: PowerUp) {
+        let duration = self.config.get_power_ups().get(kind).duration;
+        self.snake.effects.retain(|effect| effect.kind != kind);
+        if duration > 0 {
+            self.snake.effects.push(ActiveEffect { kind, ticks_left: duration });
         }
     }
 
     fn snake_can_move(&self, target: &CoordWithDirection) -> bool {
+        let ghost = self.snake.has_effect(PowerUp::Ghost);
         match self[&target.coord] {
             ItemType::Nothing => true,
-            ItemType::Food => true,
+            ItemType::Food(_) => true,
+            ItemType::PowerUp(_) => true,
             // if size is not increasing, then the current tail square will be open
-            ItemType::SnakeTail => self.snake.growing <= 0,
+            ItemType::SnakeTail => self.snake.growing <= 0 || ghost,
+            ItemType::SnakeBit | ItemType::SnakeHead if ghost => true,
+            ItemType::Wall => false,
             _ => {
-                println!("{:#?} has {:#?}", target, self[&target.coord]);
+                log::debug!("can't move to {:?}, it has {:?}", target, self[&target.coord]);
                 false
             }
         }
@@ -205,28 +560,58 @@ impl GameState {
         // advance the head
         let old_head = self.snake.body.front_mut().unwrap();
         old_head.dir_next = new_head.dir_prev.get_opposite();
-        let coord = old_head.coord.clone();
-        self[&coord] = ItemType::SnakeBit;
+        let coord = old_head.coord;
+        self.set_item(&coord, ItemType::SnakeBit);
 
-        self.snake.body.push_front(*new_head);
-        self[&new_head.coord] = ItemType::SnakeHead;
+        let replaced = self.snake.body.push_front(*new_head);
+        if let Some(delta) = self.history.current_mut() {
+            delta.moved = Some(replaced);
+        }
+        self.set_item(&new_head.coord, ItemType::SnakeHead);
     }
 
     fn bring_up_tail(&mut self) {
-        let old_tail = self.snake.body.pop_back().unwrap().clone();
-        self[&old_tail.coord] = ItemType::Nothing;
+        let old_tail = self.snake.body.pop_back().unwrap();
+        if let Some(delta) = self.history.current_mut() {
+            delta.tails.push(old_tail);
+        }
+        // a ghost can be on top of itself, so the cell may still be taken
+        if !self.snake.body.contains(&old_tail.coord) {
+            self.set_item(&old_tail.coord, ItemType::Nothing);
+        }
 
+        let head = self.snake.body.front().unwrap().coord;
         let new_tail: &mut CoordWithDirection = self.snake.body.back_mut().unwrap();
-        let coord = new_tail.coord.clone();
-        self[&coord] = ItemType::SnakeTail;
+        let coord = new_tail.coord;
+        if coord != head {
+            self.set_item(&coord, ItemType::SnakeTail);
+        }
     }
 
-    fn move_snake(&mut self, new_head: &mut CoordWithDirection) {
-        if self[&new_head.coord] == ItemType::Food {
-            self.snake.growing += SNAKE_GROWTH_PER_FOOD;
-            self.drop_new_food();
+    fn move_snake(&mut self, new_head: &mut CoordWithDirection) -> StateTransition {
+        let eaten = match self[&new_head.coord] {
+            ItemType::Food(kind) => Some(kind),
+            _ => None,
+        };
+        if let Some(kind) = eaten {
+            self.snake.growing += self.config.get_food().get(kind).growth;
+            self.events.push(GameEvent::FoodEaten(new_head.coord, kind));
         }
 
+        if let ItemType::PowerUp(kind) = self[&new_head.coord] {
+            self.power_up = None;
+            self.start_effect(kind);
+            self.events.push(GameEvent::PowerUpCollected(new_head.coord, kind));
+        }
+
+        // the old head still says which way the snake came in to it
+        let turned = self.snake.body.front().unwrap().dir_prev != new_head.dir_prev;
+        if turned {
+            self.events.push(GameEvent::DirectionChanged(new_head.dir_next));
+        }
+
+        let length = self.snake.body.len();
+
         self.advance_head(new_head);
 
         if self.snake.growing <= 0 {
@@ -234,10 +619,15 @@ impl GameState {
             self.bring_up_tail();
 
             if self.snake.growing < 0 {
-                // bring up the tail by one more
-                self.bring_up_tail();
+                if self.snake.body.len() > MINIMUM_SNAKE_LENGTH as usize {
+                    // bring up the tail by one more
+                    self.bring_up_tail();
 
-                self.snake.growing += 1;
+                    self.snake.growing += 1;
+                } else {
+                    // the snake can't shrink any further, so forget the rest
+                    self.snake.growing = 0;
+                }
             }
         } else if self.snake.growing > 0 {
             /*
@@ -246,139 +636,411 @@ impl GameState {
              * */
             self.snake.growing -= 1;
         }
+
+        if self.snake.body.len() > length {
+            self.events.push(GameEvent::Grew);
+        } else if self.snake.body.len() < length {
+            self.events.push(GameEvent::Shrank);
+        }
+
+        let double = self.snake.has_effect(PowerUp::DoubleScore);
+        let rule = eaten.map(|kind| {
+            let mut rule = self.config.get_food().get(kind);
+            if double {
+                rule.value *= 2;
+            }
+            rule
+        });
+        self.stats.record_move(self.snake.body.len(), turned, rule, &self.config.get_score_formula());
+
+        // new food goes down once the tail has moved so the square it left is a candidate
+        if eaten.is_some() {
+            if !self.drop_new_food() {
+                return StateTransition::Stop(self.outcome(GameOverCause::BoardCleared, new_head.coord));
+            }
+            self.drop_power_up();
+        }
+
+        if self.snake.has_effect(PowerUp::Magnet) {
+            self.attract_food();
+        }
+
+        StateTransition::Continue
     }
 
-    fn drop_new_food(&mut self) {
-        for _ in 0..100 {
-            let at = (
-                rand::thread_rng().gen_range(self.xrange.0..=self.xrange.1),
-                rand::thread_rng().gen_range(self.yrange.0..=self.yrange.1),
-            );
-            if self[&at] == ItemType::Nothing {
-                self[&at] = ItemType::Food;
-                break;
+    // now and then a power-up goes down with the food, as long as there isn't one already
+    fn drop_power_up(&mut self) {
+        if self.power_up.is_some() || self.free.len() == 0 {
+            return;
+        }
+
+        if let Some(kind) = self.config.get_power_ups().pick(&mut self.rng) {
+            let pick = self.rng.gen_range(0..self.free.len());
+            let at = self.grid_to_game(&self.free.get(pick));
+            self.set_item(&at, ItemType::PowerUp(kind));
+            self.power_up = Some(at);
+            self.events.push(GameEvent::PowerUpSpawned(at, kind));
+        }
+    }
+
+    // every food on the board takes a step towards the head, if the cell it would go to is empty
+    fn attract_food(&mut self) {
+        let head = self.snake.body.front().unwrap().coord;
+        let wrap = self.get_wall_policy() == WallPolicy::Wrap;
+
+        let mut food = Vec::new();
+        for (y, row) in self.world.iter().enumerate() {
+            for (x, item) in row.iter().enumerate() {
+                if let ItemType::Food(kind) = item {
+                    food.push((self.grid_to_game(&Coord::new(x as i32, y as i32)), *kind));
+                }
+            }
+        }
+
+        for (at, kind) in food {
+            // the step that brings it nearest, across the edges if they wrap; the first one on a tie
+            let mut nearest = (self.board.distance(&at, &head, wrap), at);
+            for direction in self.board.directions() {
+                let mut to = self.board.neighbor(&at, *direction);
+                if wrap {
+                    to = self.wrap(&to);
+                }
+                if !self.in_bounds(&to) {
+                    continue;
+                }
+                let distance = self.board.distance(&to, &head, wrap);
+                if distance < nearest.0 {
+                    nearest = (distance, to);
+                }
+            }
+
+            let to = nearest.1;
+            if to != at && self[&to] == ItemType::Nothing {
+                self.set_item(&at, ItemType::Nothing);
+                self.set_item(&to, ItemType::Food(kind));
             }
         }
     }
 
+    // returns false when there is nowhere left to put food
+    fn drop_new_food(&mut self) -> bool {
+        // a power-up waiting on the last open cell hasn't filled the board, so the food takes its place
+        if self.free.len() == 0 {
+            match self.power_up.take() {
+                Some(at) => self.set_item(&at, ItemType::Nothing),
+                None => return false,
+            }
+        }
+
+        let pick = self.rng.gen_range(0..self.free.len());
+        let at = self.grid_to_game(&self.free.get(pick));
+        let kind = self.config.get_food().pick(&mut self.rng).unwrap(); // the config checked some food has weight
+        self.set_item(&at, ItemType::Food(kind));
+        self.events.push(GameEvent::FoodSpawned(at, kind));
+
+        true
+    }
+
+    // every write to the world goes through here so the free cells stay in step with it
+    fn set_item(&mut self, at: &Coord, item: ItemType) {
+        let g = self.game_to_grid(at);
+
+        let free_at = if item == ItemType::Nothing {
+            self.free.insert(&g);
+            None
+        } else {
+            self.free.remove(&g)
+        };
+
+        if let Some(delta) = self.history.current_mut() {
+            delta.cells.push((g, self.world[g.y as usize][g.x as usize], free_at));
+        }
+
+        self.world[g.y as usize][g.x as usize] = item;
+    }
+
     fn initialize_snake(&mut self) {
-        for y in (self.yrange.1 - INITIAL_SNAKE_LENGTH + 0) .. self.yrange.1 {
-            let at = Coord { x: 0, y: y };
+        let heading = self.config.get_heading();
+        let length = self.config.get_initial_length();
+        let mut at = self.config.get_spawn();
+
+        let mut cells = Vec::with_capacity(length as usize);
 
-            println!("init snake: x: {}, y: {}", at.x, at.y);
+        // the config has already checked that the whole snake is on the board
+        for i in 0..length {
+            if i > 0 {
+                at = self.board.neighbor(&at, heading.get_opposite());
+            }
 
-            if y == 0 {
-                self[&at] = ItemType::SnakeHead;
-            } else if y < (INITIAL_SNAKE_LENGTH - 1) {
-                self[&at] = ItemType::SnakeBit;
+            log::trace!("init snake: x: {}, y: {}", at.x, at.y);
+
+            if i == 0 {
+                self.set_item(&at, ItemType::SnakeHead);
+            } else if i < (length - 1) {
+                self.set_item(&at, ItemType::SnakeBit);
             } else {
-                self[&at] = ItemType::SnakeTail;
+                self.set_item(&at, ItemType::SnakeTail);
             }
 
-            self.snake.body.push_back(CoordWithDirection {
-                dir_next: Direction::Up,
+            cells.push(at);
+        }
+
+        // the body only grows at the head, so it goes on tail first
+        for at in cells.into_iter().rev() {
+            self.snake.body.push_front(CoordWithDirection {
+                dir_next: heading,
                 coord: at,
-                dir_prev: Direction::Down,
+                dir_prev: heading.get_opposite(),
             });
         }
     }
 
+    // goes to the log at debug level, head first
     pub fn print_snake(&self) {
         for s in self.snake.body.iter() {
-            println!("{:?}:{}:{:?}", s.dir_next, s.coord, s.dir_prev);
+            log::debug!("{:?}:{}:{:?}", s.dir_next, s.coord, s.dir_prev);
+        }
+    }
+
+    fn outcome(&self, cause: GameOverCause, at: Coord) -> GameOutcome {
+        GameOutcome {
+            cause,
+            tick: self.tick,
+            at,
+            length: self.snake.body.len(),
+            score: self.stats.get_score(),
         }
     }
 
+    pub(crate) fn quit_outcome(&self) -> GameOutcome {
+        let head = self.snake.body.front().unwrap(); // all snakes have a head
+        self.outcome(GameOverCause::Quit, head.coord)
+    }
+
     fn try_move_snake(&mut self) -> StateTransition {
 
         // self.print_snake();
 
         let old_head = self.snake.body.front().unwrap();
 
-        match self.try_create_target(&old_head) {
+        match self.try_create_target(old_head) {
             Some(mut new_head) => {
                 if self.snake_can_move(&new_head) {
-                    self.move_snake(&mut new_head);
-                    return StateTransition::Continue;
+                    self.move_snake(&mut new_head)
+                } else if self[&new_head.coord].is_terrain() {
+                    StateTransition::Stop(self.outcome(GameOverCause::HitWall, new_head.coord))
                 } else {
-                    return StateTransition::Stop;
+                    StateTransition::Stop(self.outcome(GameOverCause::HitSelf, new_head.coord))
                 }
             }
-            None => StateTransition::Stop,
+            None => {
+                let target = self.board.neighbor(&old_head.coord, old_head.dir_next);
+                StateTransition::Stop(self.outcome(GameOverCause::HitWall, target))
+            }
         }
     }
 
     fn try_create_target(&self, a: &CoordWithDirection) -> Option<CoordWithDirection> {
 
-        let target = a.coord.calculate_neighbor(a.dir_next);
-
-        if target.x >= self.xrange.0 {
-            if target.x <= self.xrange.1 {
-                if target.y >= self.yrange.0 {
-                    if target.y <= self.yrange.1 {
-                        return Some(
-                            CoordWithDirection {
-                                dir_next: a.dir_next,
-                                coord: target,
-                                dir_prev: a.dir_next.get_opposite(),
-                            }
-                        )
-                    }
+        let target = self.board.neighbor(&a.coord, a.dir_next);
+
+        if self.in_bounds(&target) {
+            return Some(
+                CoordWithDirection {
+                    dir_next: a.dir_next,
+                    coord: target,
+                    dir_prev: a.dir_next.get_opposite(),
                 }
+            )
+        }
+
+        match self.config.get_wall_policy() {
+            WallPolicy::Die => {
+                log::debug!("failed to create target from {:?} and {:?}: {}", a, a.dir_next, target);
+                None
+            }
+            WallPolicy::Wrap => Some(
+                CoordWithDirection {
+                    dir_next: a.dir_next,
+                    coord: self.wrap(&target),
+                    dir_prev: a.dir_next.get_opposite(),
+                }
+            ),
+            WallPolicy::Bounce => {
+                /*
+                 * turn along the wall instead of going through it, the smallest turn first and
+                 * clockwise before anticlockwise; a square board only has the quarter turns, a
+                 * hex one has sharper turns after. if no way is on the board (or none is open, in
+                 * which case the first one on the board is returned and the move fails) the snake
+                 * dies.
+                 * */
+                let mut turns = Vec::new();
+                let (mut clockwise, mut anticlockwise) = (a.dir_next, a.dir_next);
+                for _ in 1..self.board.directions().len() / 2 {
+                    clockwise = self.board.turn(clockwise, true);
+                    anticlockwise = self.board.turn(anticlockwise, false);
+                    turns.extend([clockwise, anticlockwise]);
+                }
+
+                let candidates: Vec<CoordWithDirection> = turns
+                    .iter()
+                    .map(|d| CoordWithDirection {
+                        dir_next: *d,
+                        coord: self.board.neighbor(&a.coord, *d),
+                        dir_prev: d.get_opposite(),
+                    })
+                    .filter(|c| self.in_bounds(&c.coord))
+                    .collect();
+
+                candidates
+                    .iter()
+                    .find(|c| self.snake_can_move(c))
+                    .or(candidates.first())
+                    .copied()
             }
         }
+    }
 
-        println!("failed to create target from {:?} and {:?}: {}", a, a.dir_next, target);
+    // the board as a level, with the snake where the config spawned it
+    pub fn to_level(&self) -> Level {
+        let mut walls = Vec::new();
+        for (y, row) in self.world.iter().enumerate() {
+            for (x, item) in row.iter().enumerate() {
+                if *item == ItemType::Wall {
+                    walls.push(self.grid_to_game(&Coord::new(x as i32, y as i32)));
+                }
+            }
+        }
 
-        None
+        Level::new(
+            self.width,
+            self.height,
+            walls,
+            self.config.get_spawn(),
+            self.config.get_heading(),
+            self.config.get_initial_length(),
+        )
     }
 
-    pub fn game_to_grid(&self, at: &Coord) -> Coord {
-        Coord {
-            x: at.x - self.xrange.0,
-            y: at.y - self.yrange.0,
+    // what is at a cell, None when it is off the board
+    pub fn get(&self, at: &Coord) -> Option<&ItemType> {
+        match self.in_bounds(at) {
+            true => Some(&self[at]),
+            false => None,
         }
     }
 
-    pub fn game_to_grid_tuple(&self, at: &(i32, i32)) -> (usize, usize) {
-        let g = (
-            (at.0 - self.xrange.0) as usize,
-            (at.1 - self.yrange.0) as usize,
-        );
-        return (g.0, g.1);
-    }
-
-    fn handle_direction(&mut self, direction: Direction) -> StateTransition {
-        let head = self.snake.body.front_mut().unwrap();
-        if head.dir_next != direction.get_disallowed() {
-            println!(
-                "changing direction from {:?} to {:?}",
-                head.dir_next, direction
-            );
-            head.dir_next = direction;
-        } else {
-            println!(
-                "not changing direction from {:?} to {:?}",
-                head.dir_next, direction
-            );
+    /*
+     * check the things the rest of GameState takes for granted: a valid config, a grid of the
+     * right size and a snake that is on the board where the grid says it is. a game that was
+     * created and played through the methods here always passes; a loaded one might not.
+     * */
+    pub fn validate(&self) -> Result<(), SnakersError> {
+        let bad = |why: &str| Err(SnakersError::BadState(why.to_string()));
+
+        self.config.validate()?;
+
+        if self.width != self.config.get_width() || self.height != self.config.get_height() {
+            return bad("board size does not match the config");
         }
-        StateTransition::Continue
+
+        if self.world.len() != self.height as usize
+            || self.world.iter().any(|row| row.len() != self.width as usize)
+        {
+            return bad("grid size does not match the board size");
+        }
+
+        if self.board != self.config.get_board() {
+            return bad("board does not match the config");
+        }
+
+        if self.snake.body.is_empty() {
+            return bad("the snake has no head");
+        }
+
+        for bit in self.snake.body.iter() {
+            match self.get(&bit.coord) {
+                None => return Err(SnakersError::OutOfBounds(bit.coord)),
+                Some(ItemType::SnakeHead | ItemType::SnakeBit | ItemType::SnakeTail) => (),
+                Some(_) => return bad("the snake is not on the grid where it should be"),
+            }
+            if !self.board.has_direction(bit.dir_next) || !self.board.has_direction(bit.dir_prev) {
+                return bad("the snake is going a way the board doesn't go");
+            }
+        }
+
+        if !self.snake.body.is_indexed(&self.board) {
+            return bad("the body index does not match the body");
+        }
+
+        // every free cell is empty and listed once, and there are as many as the grid has empty cells
+        if !self.free.is_indexed(self.width, self.height) {
+            return bad("the free cell index does not match the free cells");
+        }
+        if self.free.iter().any(|g| self.world[g.y as usize][g.x as usize] != ItemType::Nothing) {
+            return bad("a free cell is not empty");
+        }
+        let free = self.world.iter().flatten().filter(|item| **item == ItemType::Nothing).count();
+        if free != self.free.len() {
+            return bad("free cells do not match the grid");
+        }
+
+        // a running effect has ticks left, or it would have ended
+        if self.snake.effects.iter().any(|effect| effect.ticks_left == 0) {
+            return bad("an effect has run out but is still running");
+        }
+
+        if let Some(at) = self.power_up {
+            if !matches!(self.get(&at), Some(ItemType::PowerUp(_))) {
+                return bad("the power-up is not on the grid where it should be");
+            }
+        }
+
+        // the queue only takes what queue_turn would have let in
+        if self.turns.len() > self.config.get_turn_queue_depth() {
+            return bad("more turns are queued than the queue holds");
+        }
+        if let Some(turn) = self.turns.iter().find(|turn| !self.board.has_direction(**turn)) {
+            return Err(SnakersError::BadState(format!("a {} board can't turn {:?}", self.board.get_shape(), turn)));
+        }
+
+        Ok(())
+    }
+
+    pub fn get_wall_policy(&self) -> WallPolicy {
+        self.config.get_wall_policy()
+    }
+
+    pub fn in_bounds(&self, at: &Coord) -> bool {
+        self.board.in_bounds(at)
+    }
+
+    // bring a coordinate that has gone off one edge back in from the opposite edge
+    pub fn wrap(&self, at: &Coord) -> Coord {
+        self.board.wrap(at)
+    }
+
+    pub fn game_to_grid(&self, at: &Coord) -> Coord {
+        self.board.to_grid(at)
+    }
+
+    pub fn grid_to_game(&self, at: &Coord) -> Coord {
+        self.board.to_game(at)
+    }
+
+    pub fn game_to_grid_tuple(&self, at: &(i32, i32)) -> (usize, usize) {
+        let g = self.board.to_grid(&Coord::new(at.0, at.1));
+        (g.x as usize, g.y as usize)
     }
 }
 
+// like slices, indexing off the board panics; GameState::get is the careful way
 impl std::ops::Index<&(i32, i32)> for GameState {
     type Output = ItemType;
 
     fn index(&self, at: &(i32, i32)) -> &Self::Output {
-        let g = self.game_to_grid_tuple(&at);
-        return &self.world[g.1][g.0];
-    }
-}
-
-impl std::ops::IndexMut<&(i32, i32)> for GameState {
-    fn index_mut(&mut self, at: &(i32, i32)) -> &mut Self::Output {
-        let g = self.game_to_grid_tuple(&at);
-        &mut self.world[g.1][g.0]
+        let g = self.game_to_grid_tuple(at);
+        &self.world[g.1][g.0]
     }
 }
 
@@ -390,8 +1052,429 @@ impl std::ops::Index<&Coord> for GameState {
     }
 }
 
-impl std::ops::IndexMut<&Coord> for GameState {
-    fn index_mut(&mut self, at: &Coord) -> &mut Self::Output {
-        &mut self[&(at.x, at.y)]
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::AStarBot;
+    use crate::controller::Controller;
+    use crate::history::DEFAULT_HISTORY_DEPTH;
+
+    fn config() -> GameConfig {
+        GameConfig::builder()
+            .size(16, 12)
+            .build()
+            .unwrap()
+    }
+
+    fn food(game: &GameState) -> Option<Coord> {
+        for (row, items) in game.get_world().iter().enumerate() {
+            if let Some(col) = items.iter().position(|item| matches!(item, ItemType::Food(_))) {
+                return Some(Coord::new(col as i32 + game.board.get_xrange().0, row as i32 + game.board.get_yrange().0));
+            }
+        }
+        None
+    }
+
+    // heads for the food, so there are turns and the snake eats; turns off to the side when it is behind
+    fn greedy_input(game: &GameState) -> InputType {
+        let head = game.get_snake().get_body().front().unwrap();
+        let target = match food(game) {
+            Some(target) => target,
+            None => return InputType::Nothing,
+        };
+
+        let towards = [
+            (target.x > head.coord.x, Direction::Right),
+            (target.x < head.coord.x, Direction::Left),
+            (target.y > head.coord.y, Direction::Down),
+            (target.y < head.coord.y, Direction::Up),
+        ];
+        let direction = match towards.iter().find(|(wanted, d)| *wanted && *d != head.dir_prev) {
+            Some((_, direction)) => *direction,
+            None if head.dir_prev == Direction::Up || head.dir_prev == Direction::Down => {
+                if head.coord.x < 0 { Direction::Right } else { Direction::Left }
+            }
+            None => {
+                if head.coord.y < 0 { Direction::Down } else { Direction::Up }
+            }
+        };
+        InputType::from(direction)
+    }
+
+    // plays a game to the end or for this many ticks, with the cells the food went down in
+    fn play(game: &mut GameState, ticks: usize) -> Vec<Coord> {
+        let mut foods: Vec<Coord> = food(game).into_iter().collect();
+        for _ in 0..ticks {
+            game.handle_input(greedy_input(game));
+            let transition = game.update_state();
+            if let Some(at) = food(game) {
+                if foods.last() != Some(&at) {
+                    foods.push(at);
+                }
+            }
+            if let StateTransition::Stop(_) = transition {
+                break;
+            }
+        }
+        foods
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let mut a = GameState::with_seed(config(), 42).unwrap();
+        let mut b = GameState::with_seed(config(), 42).unwrap();
+        assert_eq!(a.get_seed(), 42);
+
+        for _ in 0..300 {
+            let input = greedy_input(&a);
+            a.handle_input(input);
+            b.handle_input(input);
+            let transition = a.update_state();
+            b.update_state();
+            assert!(a.get_world() == b.get_world());
+            if let StateTransition::Stop(_) = transition {
+                break;
+            }
+        }
+    }
+
+    // a short snake heading right with its head at `spawn`, on a 10x8 board that runs from -5 to 4 and -4 to 3
+    fn heading_right(wall_policy: WallPolicy, spawn: Coord) -> GameState {
+        let config = GameConfig::builder()
+            .size(10, 8)
+            .wall_policy(wall_policy)
+            .initial_length(3)
+            .spawn(spawn)
+            .heading(Direction::Right)
+            .build()
+            .unwrap();
+        GameState::with_seed(config, 0).unwrap()
+    }
+
+    fn head(game: &GameState) -> CoordWithDirection {
+        *game.get_snake().get_body().front().unwrap()
+    }
+
+    #[test]
+    fn wrap_comes_in_the_other_side() {
+        let mut game = heading_right(WallPolicy::Wrap, Coord::new(4, 1));
+        assert!(matches!(game.update_state(), StateTransition::Continue));
+        assert_eq!((head(&game).coord, head(&game).dir_next), (Coord::new(-5, 1), Direction::Right));
+        game.validate().unwrap();
+
+        // and the same going up off the top
+        game.handle_input(InputType::Up);
+        for _ in 0..6 {
+            assert!(matches!(game.update_state(), StateTransition::Continue));
+        }
+        assert_eq!(head(&game).coord, Coord::new(-5, 3));
+        game.validate().unwrap();
+    }
+
+    #[test]
+    fn die_at_the_edge() {
+        let mut game = heading_right(WallPolicy::Die, Coord::new(4, 1));
+        match game.update_state() {
+            StateTransition::Stop(outcome) => assert_eq!(outcome.cause, GameOverCause::HitWall),
+            StateTransition::Continue => panic!("went through the wall"),
+        }
+    }
+
+    #[test]
+    fn bounce_turns_along_the_edge() {
+        // clockwise first, which from right is down
+        let mut game = heading_right(WallPolicy::Bounce, Coord::new(4, 1));
+        assert!(matches!(game.update_state(), StateTransition::Continue));
+        assert_eq!((head(&game).coord, head(&game).dir_next), (Coord::new(4, 2), Direction::Down));
+        game.validate().unwrap();
+
+        // then anticlockwise when down is off the board too
+        let mut game = heading_right(WallPolicy::Bounce, Coord::new(4, 3));
+        assert!(matches!(game.update_state(), StateTransition::Continue));
+        assert_eq!((head(&game).coord, head(&game).dir_next), (Coord::new(4, 2), Direction::Up));
+        game.validate().unwrap();
+    }
+
+    #[test]
+    fn turns_queue_up_one_a_tick() {
+        let mut game = heading_right(WallPolicy::Wrap, Coord::new(0, 0));
+        for input in [InputType::Up, InputType::Left, InputType::Down] {
+            game.handle_input(input);
+        }
+        assert_eq!(game.turns, [Direction::Up, Direction::Left, Direction::Down]);
+
+        // the queue is full
+        game.handle_input(InputType::Right);
+        assert_eq!(game.turns.len(), 3);
+
+        for direction in [Direction::Up, Direction::Left, Direction::Down, Direction::Down] {
+            game.update_state();
+            assert_eq!(head(&game).dir_next, direction);
+        }
+        assert!(game.turns.is_empty());
+    }
+
+    #[test]
+    fn turns_that_go_nowhere_are_dropped() {
+        let mut game = heading_right(WallPolicy::Wrap, Coord::new(0, 0));
+
+        // back on itself, straight on, and a way a square board doesn't go
+        for input in [InputType::Left, InputType::Right, InputType::UpRight] {
+            game.handle_input(input);
+        }
+        assert!(game.turns.is_empty());
+
+        // reversing is checked against the last turn queued, not the heading
+        game.handle_input(InputType::Up);
+        game.handle_input(InputType::Down);
+        game.handle_input(InputType::Up);
+        assert_eq!(game.turns, [Direction::Up]);
+        game.handle_input(InputType::Left);
+        assert_eq!(game.turns, [Direction::Up, Direction::Left]);
+
+        // a queue of one keeps only the first
+        let config = GameConfig::builder()
+            .size(10, 8)
+            .initial_length(3)
+            .heading(Direction::Right)
+            .turn_queue_depth(1)
+            .build()
+            .unwrap();
+        let mut game = GameState::with_seed(config, 0).unwrap();
+        game.handle_input(InputType::Up);
+        game.handle_input(InputType::Left);
+        assert_eq!(game.turns, [Direction::Up]);
+    }
+
+    // walls up every cell that's still open
+    fn fill_open_cells(game: &mut GameState) {
+        for y in 0..game.get_height() as i32 {
+            for x in 0..game.get_width() as i32 {
+                let at = game.grid_to_game(&Coord::new(x, y));
+                if game[&at] == ItemType::Nothing {
+                    game.set_item(&at, ItemType::Wall);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn power_up_on_last_cell_is_not_a_win() {
+        let config = GameConfig::builder().size(10, 8).wall_policy(WallPolicy::Wrap).build().unwrap();
+        let mut game = GameState::with_seed(config, 5).unwrap();
+        let head = game.get_snake().get_body().front().unwrap().coord;
+        let food = game.wrap(&head.calculate_neighbor(game.get_heading()));
+
+        // food right in front of the head, a power-up on the only other open cell
+        for y in 0..game.get_height() as i32 {
+            for x in 0..game.get_width() as i32 {
+                let at = game.grid_to_game(&Coord::new(x, y));
+                if let ItemType::Food(_) = game[&at] {
+                    game.set_item(&at, ItemType::Nothing);
+                }
+            }
+        }
+        let power_up = game.grid_to_game(&Coord::new(0, 0));
+        game.set_item(&food, ItemType::Food(FoodKind::Regular));
+        game.set_item(&power_up, ItemType::PowerUp(PowerUp::Ghost));
+        game.power_up = Some(power_up);
+        fill_open_cells(&mut game);
+        assert_eq!(game.free.len(), 0);
+
+        // eating grows the snake, so the tail stays put and the power-up's cell is the only one left
+        assert!(matches!(game.update_state(), StateTransition::Continue));
+        assert!(matches!(game[&power_up], ItemType::Food(_)));
+        assert_eq!(game.power_up, None);
+        assert_eq!(game.free.len(), 0);
+        game.validate().unwrap();
+    }
+
+    #[test]
+    fn magnet_pulls_food_across_the_edge() {
+        for policy in [WallPolicy::Wrap, WallPolicy::Die] {
+            let config = GameConfig::builder()
+                .size(10, 8)
+                .wall_policy(policy)
+                .initial_length(3)
+                .spawn(Coord::new(3, 0))
+                .heading(Direction::Up)
+                .build()
+                .unwrap();
+            let mut game = GameState::with_seed(config, 5).unwrap();
+            let head = game.get_snake().get_body().front().unwrap().coord;
+            for y in 0..game.get_height() as i32 {
+                for x in 0..game.get_width() as i32 {
+                    let at = game.grid_to_game(&Coord::new(x, y));
+                    if let ItemType::Food(_) = game[&at] {
+                        game.set_item(&at, ItemType::Nothing);
+                    }
+                }
+            }
+
+            // six cells along the head's row, which is four the other way round
+            let x = if head.x - 6 >= game.board.get_xrange().0 { head.x - 6 } else { head.x + 6 };
+            let at = Coord::new(x, head.y);
+            let away = Coord::new(at.x - (head.x - at.x).signum(), at.y);
+            let toward = Coord::new(at.x + (head.x - at.x).signum(), at.y);
+            game.set_item(&at, ItemType::Food(FoodKind::Regular));
+
+            game.attract_food();
+            let moved = match policy {
+                WallPolicy::Wrap => game.wrap(&away),
+                _ => toward,
+            };
+            assert_eq!(game[&moved], ItemType::Food(FoodKind::Regular), "{:?}", policy);
+            assert_eq!(game[&at], ItemType::Nothing);
+        }
+    }
+
+    #[test]
+    fn different_seed_different_food() {
+        let one = play(&mut GameState::with_seed(config(), 1).unwrap(), 300);
+        let two = play(&mut GameState::with_seed(config(), 2).unwrap(), 300);
+
+        // some food has to get eaten for there to be a sequence
+        assert!(one.len() > 2);
+        assert_ne!(one, two);
+    }
+
+    // wrapping, so a game goes on long enough to have a history worth taking back
+    fn wrap_config() -> GameConfig {
+        config().to_builder().wall_policy(WallPolicy::Wrap).build().unwrap()
+    }
+
+    // the A* bot's inputs for a game, so there are turns and the snake eats
+    fn bot_inputs(seed: u64, ticks: usize) -> Vec<InputType> {
+        let mut game = GameState::with_seed(wrap_config(), seed).unwrap();
+        let mut bot = AStarBot::default();
+        let mut inputs = Vec::new();
+        for _ in 0..ticks {
+            let input = bot.next_input(&game);
+            inputs.push(input);
+            game.handle_input(input);
+            if let StateTransition::Stop(_) = game.update_state() {
+                break;
+            }
+        }
+        inputs
+    }
+
+    /*
+     * plays the inputs one a tick, with the state hash each tick started from (its input queued,
+     * which is where a rewind goes back to) and the hash after each, the first before any
+     * */
+    fn play_ticks(game: &mut GameState, inputs: &[InputType]) -> (Vec<u64>, Vec<u64>) {
+        let (mut started, mut ended) = (Vec::new(), vec![game.state_hash()]);
+        for input in inputs {
+            game.handle_input(*input);
+            started.push(game.state_hash());
+            let transition = game.update_state();
+            ended.push(game.state_hash());
+            if let StateTransition::Stop(_) = transition {
+                break;
+            }
+        }
+        (started, ended)
+    }
+
+    #[test]
+    fn rewind_and_redo_match_the_forward_run() {
+        let inputs = bot_inputs(42, 300);
+        let mut game = GameState::with_seed(wrap_config(), 42).unwrap();
+        let (started, ended) = play_ticks(&mut game, &inputs);
+        let end = started.len();
+        let forward = game.clone();
+        assert!(end > 50);
+        assert_eq!(game.get_rewindable(), end);
+
+        for k in [1, 7, 50, end] {
+            assert_eq!(game.rewind(k), k);
+            assert_eq!(game.state_hash(), started[end - k]);
+            assert_eq!(game.get_tick(), (end - k) as u64);
+            game.validate().unwrap();
+
+            assert_eq!(game.redo(k), k);
+            assert_eq!(game.state_hash(), ended[end]);
+            assert_eq!(game, forward);
+        }
+
+        // and one tick at a time, each on the way
+        for k in 1..=end {
+            assert_eq!(game.rewind(1), 1);
+            assert_eq!(game.state_hash(), started[end - k]);
+        }
+        assert_eq!(game.rewind(1), 0);
+        for hash in &ended[1..] {
+            assert_eq!(game.redo(1), 1);
+            assert_eq!(game.state_hash(), *hash);
+        }
+        assert_eq!(game.redo(1), 0);
+
+        // playing the same inputs again from part way back comes out the same as well
+        assert_eq!(game.rewind(30), 30);
+        for (i, input) in inputs[end - 30..end].iter().enumerate() {
+            game.handle_input(*input);
+            game.update_state();
+            assert_eq!(game.state_hash(), ended[end - 29 + i]);
+        }
+    }
+
+    #[test]
+    fn new_tick_after_rewind_drops_redo() {
+        let inputs = bot_inputs(42, 100);
+        let mut game = GameState::with_seed(wrap_config(), 42).unwrap();
+        let (started, _) = play_ticks(&mut game, &inputs);
+        let end = started.len();
+
+        game.rewind(5);
+        assert_eq!(game.redo(2), 2);
+        assert_eq!(game.get_redoable(), 3);
+
+        // queueing a turn alone doesn't, playing a tick does
+        game.handle_input(InputType::Nothing);
+        assert_eq!(game.get_redoable(), 3);
+        game.update_state();
+        assert_eq!(game.get_redoable(), 0);
+        assert_eq!(game.redo(1), 0);
+        assert_eq!(game.get_tick(), (end - 2) as u64);
+        assert_eq!(game.get_rewindable(), end - 2);
+    }
+
+    #[test]
+    fn history_stays_within_its_depth() {
+        let mut game = GameState::with_seed(wrap_config(), 42).unwrap();
+        assert_eq!(game.get_history_depth(), DEFAULT_HISTORY_DEPTH);
+        game.set_history_depth(20);
+
+        let inputs = bot_inputs(42, 100);
+        let mut started = Vec::new();
+        for input in &inputs {
+            game.handle_input(*input);
+            started.push(game.state_hash());
+            let transition = game.update_state();
+            assert_eq!(game.get_rewindable(), started.len().min(20));
+            if let StateTransition::Stop(_) = transition {
+                break;
+            }
+        }
+        let (end, last) = (started.len(), game.state_hash());
+        assert!(end > 20);
+
+        // only as far back as it kept
+        assert_eq!(game.rewind(100), 20);
+        assert_eq!(game.state_hash(), started[end - 20]);
+        assert_eq!(game.redo(100), 20);
+        assert_eq!(game.state_hash(), last);
+
+        // shrinking drops the oldest, and none keeps nothing
+        game.set_history_depth(5);
+        assert_eq!(game.get_rewindable(), 5);
+        assert_eq!(game.rewind(100), 5);
+        assert_eq!(game.state_hash(), started[end - 5]);
+        game.set_history_depth(0);
+        game.update_state();
+        assert_eq!((game.get_rewindable(), game.get_redoable()), (0, 0));
+        assert_eq!(game.rewind(1), 0);
     }
 }
@@ -0,0 +1,282 @@
+use crate::astar::{path_to_food, roomiest_move};
+use crate::controller::{is_safe_move, neighbor, Controller};
+use crate::coord::Coord;
+use crate::direction::Direction;
+use crate::food::FoodKind;
+use crate::game::{GameState, ItemType};
+use crate::inputtype::InputType;
+use crate::topology::Topology;
+
+/*
+ * Follows a cycle through every cell on the board. Once the body lies along the cycle, with no gaps,
+ * the head is always chasing the tail round it, so the snake can't run into itself and fills the
+ * board in the end. While the snake is short it cuts across the cycle towards the food. A cut
+ * leaves cells behind that the head can't get back to until the tail has passed them, so it only
+ * cuts where that leaves plenty of room ahead, and stops once the snake spans half the cycle.
+ *
+ * A cycle needs a board with no walls and an even number of cells; on any other board this plays
+ * like the A* bot.
+ * */
+#[derive(Default)]
+pub struct HamiltonianBot {
+    cycle: Option<Cycle>,
+}
+
+// no shortcuts once the stretch of cycle from the tail to the head is this share of it, in percent
+const SHORTCUT_LIMIT: usize = 50;
+
+// cells kept between the head and the tail on top of any growth still to come
+const SHORTCUT_MARGIN: usize = 3;
+
+// and room for the snake to eat this many more of the biggest food before the holes clear
+const SHORTCUT_FOODS: usize = 4;
+
+impl Controller for HamiltonianBot {
+    fn next_input(&mut self, game: &GameState) -> InputType {
+        let fits = matches!(&self.cycle, Some(cycle) if cycle.width == game.get_width() && cycle.height == game.get_height());
+        if !fits {
+            self.cycle = Cycle::build(game);
+        }
+
+        let direction = match &self.cycle {
+            Some(cycle) => cycle.next_move(game),
+            None => path_to_food(game).or_else(|| roomiest_move(game)),
+        };
+
+        match direction {
+            Some(direction) if direction == game.get_heading() => InputType::Nothing,
+            Some(direction) => InputType::from(direction),
+            None => InputType::Nothing,
+        }
+    }
+}
+
+struct Cycle {
+    width: u32,
+    height: u32,
+    // where each grid cell comes on the cycle, row by row
+    order: Vec<usize>,
+}
+
+impl Cycle {
+    /*
+     * Along the top row, then back and forth along the rows below leaving out the first column,
+     * then up the first column to the start. That closes when the number of rows is even; with an
+     * odd number of rows and an even number of columns the same thing works on its side. It only
+     * steps along a row or straight up and down a column, and those cells are next to each other
+     * on a hex board too, so the same cycle does for both.
+     * */
+    fn build(game: &GameState) -> Option<Self> {
+        let (width, height) = (game.get_width(), game.get_height());
+        if !game.get_config().get_walls().is_empty() || width < 2 || height < 2 {
+            return None;
+        }
+
+        let (long, short, transposed) = match (height % 2, width % 2) {
+            (0, _) => (width, height, false),
+            (_, 0) => (height, width, true),
+            _ => return None,
+        };
+
+        let mut path = Vec::new();
+        for a in 0..long {
+            path.push((a, 0));
+        }
+        for b in 1..short {
+            if b % 2 == 1 {
+                for a in (1..long).rev() {
+                    path.push((a, b));
+                }
+            } else {
+                for a in 1..long {
+                    path.push((a, b));
+                }
+            }
+        }
+        for b in (1..short).rev() {
+            path.push((0, b));
+        }
+
+        let mut order = vec![0; width as usize * height as usize];
+        for (i, (a, b)) in path.into_iter().enumerate() {
+            let (x, y) = if transposed { (b, a) } else { (a, b) };
+            order[y as usize * width as usize + x as usize] = i;
+        }
+
+        Some(Cycle { width, height, order })
+    }
+
+    fn position(&self, game: &GameState, at: &Coord) -> usize {
+        let g = game.game_to_grid(at);
+        self.order[g.y as usize * self.width as usize + g.x as usize]
+    }
+
+    // steps forward along the cycle from one cell to another
+    fn distance(&self, from: usize, to: usize) -> usize {
+        (to + self.order.len() - from) % self.order.len()
+    }
+
+    // whether each bit of the body comes after the one behind it, going round from the tail
+    fn is_lined_up(&self, game: &GameState) -> bool {
+        let body = game.get_snake().get_body();
+        let tail = self.position(game, &body.back().unwrap().coord);
+
+        let mut last = 0;
+        for bit in body.iter().rev().skip(1) {
+            let d = self.distance(tail, self.position(game, &bit.coord));
+            if d <= last {
+                return false;
+            }
+            last = d;
+        }
+        true
+    }
+
+    fn next_move(&self, game: &GameState) -> Option<Direction> {
+        let snake = game.get_snake();
+        let head = snake.get_body().front().unwrap().coord; // all snakes have a head
+        let here = self.position(game, &head);
+
+        let moves: Vec<(Direction, usize)> = game.get_board().directions().iter()
+            .filter(|d| **d != game.get_heading().get_opposite() && is_safe_move(game, **d))
+            .map(|d| (*d, self.position(game, &neighbor(game, &head, *d).unwrap())))
+            .collect();
+
+        // the snake starts out straight, which doesn't always go the cycle's way
+        if !self.is_lined_up(game) {
+            return match moves.iter().find(|(_, at)| self.distance(here, *at) == 1) {
+                Some((direction, _)) => Some(*direction),
+                None => roomiest_move(game),
+            };
+        }
+
+        let along = moves.iter().find(|(_, at)| self.distance(here, *at) == 1).map(|(d, _)| *d);
+
+        /*
+         * cells a shortcut jumps over stay empty until the tail has gone past them, and the head
+         * can't use them until then, so the limit is on how much of the cycle the snake spans
+         * rather than on how long it is
+         * */
+        let tail = self.position(game, &snake.get_body().back().unwrap().coord);
+        let room = self.distance(here, tail);
+        let limit = self.order.len() * SHORTCUT_LIMIT / 100;
+        let food = match self.nearest_food(game, here) {
+            Some(food) => food,
+            None => return along.or_else(|| roomiest_move(game)),
+        };
+
+        // what's left between the head and the tail has to cover any growing, and the holes too
+        let length = snake.get_body().len();
+        let grow = snake.get_growing().max(0) as usize + SHORTCUT_FOODS * self.most_growth(game) + SHORTCUT_MARGIN;
+        let fits = |at: usize| {
+            let span = self.distance(tail, at) + 1;
+            span < limit && span - (length + 1) + grow < room - self.distance(here, at)
+        };
+
+        moves.iter()
+            .filter(|(_, at)| self.distance(here, *at) < room && fits(*at))
+            .min_by_key(|(_, at)| self.distance(*at, food))
+            .map(|(d, _)| *d)
+            .or(along)
+            // the cycle only runs into the tail when the holes haven't cleared in time
+            .or_else(|| roomiest_move(game))
+    }
+
+    // the food that comes up soonest going round the cycle from here
+    fn nearest_food(&self, game: &GameState, here: usize) -> Option<usize> {
+        let mut nearest = None;
+        for (y, row) in game.get_world().iter().enumerate() {
+            for (x, item) in row.iter().enumerate() {
+                if let ItemType::Food(_) = item {
+                    let at = self.order[y * self.width as usize + x];
+                    if nearest.is_none_or(|n| self.distance(here, at) < self.distance(here, n)) {
+                        nearest = Some(at);
+                    }
+                }
+            }
+        }
+        nearest
+    }
+
+    // the most any one food can make the snake grow
+    fn most_growth(&self, game: &GameState) -> usize {
+        FoodKind::ALL.iter()
+            .map(|kind| game.get_config().get_food().get(*kind))
+            .filter(|rule| rule.weight > 0)
+            .map(|rule| rule.growth.max(0) as usize)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{GameConfig, WallPolicy};
+    use crate::game::{GameOverCause, StateTransition};
+    use crate::topology::Shape;
+
+    fn game(shape: Shape, wall_policy: WallPolicy, width: u32, height: u32, seed: u64) -> GameState {
+        let config = GameConfig::builder()
+            .shape(shape)
+            .wall_policy(wall_policy)
+            .size(width, height)
+            .build()
+            .unwrap();
+        GameState::with_seed(config, seed).unwrap()
+    }
+
+    #[test]
+    fn cycle_visits_every_cell_once() {
+        // along the rows, with an odd number of columns, on its side, and on a hex board
+        let sizes = [(Shape::Square, 10, 8), (Shape::Square, 15, 8), (Shape::Square, 12, 9), (Shape::Hex, 10, 8)];
+        for (shape, width, height) in sizes {
+            let game = game(shape, WallPolicy::Die, width, height, 0);
+            let cycle = Cycle::build(&game).unwrap();
+
+            let mut seen = vec![false; cycle.order.len()];
+            for i in &cycle.order {
+                assert!(!seen[*i], "{} comes up twice on {}x{}", i, width, height);
+                seen[*i] = true;
+            }
+
+            // and each cell on it is a step from the one before, round to the start
+            let mut cells = vec![Coord::new(0, 0); cycle.order.len()];
+            for y in 0..height as i32 {
+                for x in 0..width as i32 {
+                    let at = game.grid_to_game(&Coord::new(x, y));
+                    cells[cycle.position(&game, &at)] = at;
+                }
+            }
+            for (i, at) in cells.iter().enumerate() {
+                let next = cells[(i + 1) % cells.len()];
+                let board = game.get_board();
+                let steps = board.directions().iter().any(|d| board.neighbor(at, *d) == next);
+                assert!(steps, "{:?} to {:?} on {}x{}", at, next, width, height);
+            }
+        }
+
+        // no cycle with an odd number of cells
+        assert!(Cycle::build(&game(Shape::Square, WallPolicy::Die, 15, 9, 0)).is_none());
+    }
+
+    #[test]
+    fn fills_the_board() {
+        let boards = [(Shape::Square, WallPolicy::Die), (Shape::Square, WallPolicy::Wrap), (Shape::Hex, WallPolicy::Die)];
+        for (shape, wall_policy) in boards {
+            for seed in 0..3 {
+                let mut game = game(shape, wall_policy, 10, 8, seed);
+                let mut bot = HamiltonianBot::default();
+                let outcome = loop {
+                    game.handle_input(bot.next_input(&game));
+                    if let StateTransition::Stop(outcome) = game.update_state() {
+                        break outcome;
+                    }
+                    assert!(game.get_tick() < 100_000, "seed {} goes on forever", seed);
+                };
+                let board = format!("{:?} {:?} seed {}", shape, wall_policy, seed);
+                assert_eq!(outcome.cause, GameOverCause::BoardCleared, "{}", board);
+            }
+        }
+    }
+}
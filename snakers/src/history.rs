@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+
+use crate::coord::Coord;
+use crate::direction::Direction;
+use crate::event::GameEvent;
+use crate::game::{CoordWithDirection, ItemType};
+use crate::powerup::ActiveEffect;
+use crate::stats::GameStats;
+
+/*
+ * What GameState keeps so it can take back its last few ticks. A tick only touches a handful of
+ * cells and the two ends of the snake, so rather than a copy of the whole game each one leaves a
+ * delta: the cells it wrote and what they held, what it did to the body, and the small things that
+ * are cheap to copy outright, the RNG's place in its stream among them. Undoing a delta puts all of
+ * that back, so playing the same tick again draws the same numbers and comes out the same.
+ *
+ * Only the last `depth` ticks are kept, oldest dropped first. Ticks that were taken back can be
+ * played again until a new one is played; all that needs is the turns that were waiting, the rest
+ * follows from the state it goes back to.
+ * */
+pub const DEFAULT_HISTORY_DEPTH: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Delta {
+    // all as they were before the tick
+    pub(crate) tick: u64,
+    pub(crate) head: CoordWithDirection,
+    pub(crate) growing: i32,
+    pub(crate) effects: Vec<ActiveEffect>,
+    pub(crate) power_up: Option<Coord>,
+    pub(crate) turns: VecDeque<Direction>,
+    pub(crate) stats: GameStats,
+    pub(crate) events: Vec<GameEvent>,
+    pub(crate) word_pos: u128,
+
+    // every cell written in grid coordinates, what it held, and its place in the free cells if it was free
+    pub(crate) cells: Vec<(Coord, ItemType, Option<usize>)>,
+    // what the body's index had where the new head went, if the head moved
+    pub(crate) moved: Option<Option<u64>>,
+    // segments that came off the tail, in the order they came off
+    pub(crate) tails: Vec<CoordWithDirection>,
+}
+
+impl Delta {
+    fn new(head: CoordWithDirection) -> Self {
+        Delta {
+            tick: 0,
+            head,
+            growing: 0,
+            effects: Vec::new(),
+            power_up: None,
+            turns: VecDeque::new(),
+            stats: GameStats::default(),
+            events: Vec::new(),
+            word_pos: 0,
+            cells: Vec::new(),
+            moved: None,
+            tails: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct History {
+    depth: usize,
+    undo: VecDeque<Delta>,
+    // the turns waiting before each tick taken back, most recently taken back last
+    redo: Vec<VecDeque<Direction>>,
+    // the tick being played, if there is room to keep it
+    current: Option<Delta>,
+}
+
+impl History {
+    pub(crate) fn new(depth: usize) -> Self {
+        History {
+            depth,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            current: None,
+        }
+    }
+
+    pub(crate) fn get_depth(&self) -> usize {
+        self.depth
+    }
+
+    pub(crate) fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        while self.undo.len() > depth {
+            self.undo.pop_front();
+        }
+    }
+
+    pub(crate) fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub(crate) fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    /*
+     * a delta for the tick about to be played, for the game to fill in. once the history is full
+     * it's the oldest one, emptied, so a long game isn't allocating on every tick. with no depth
+     * nothing is kept and there's nothing to fill in.
+     * */
+    pub(crate) fn begin(&mut self, head: CoordWithDirection) -> Option<&mut Delta> {
+        if self.depth == 0 {
+            return None;
+        }
+
+        let mut delta = match self.undo.len() >= self.depth {
+            true => self.undo.pop_front().unwrap(),
+            false => Delta::new(head),
+        };
+        delta.head = head;
+        delta.cells.clear();
+        delta.moved = None;
+        delta.tails.clear();
+
+        self.current = Some(delta);
+        self.current.as_mut()
+    }
+
+    pub(crate) fn current_mut(&mut self) -> Option<&mut Delta> {
+        self.current.as_mut()
+    }
+
+    pub(crate) fn commit(&mut self) {
+        if let Some(delta) = self.current.take() {
+            self.undo.push_back(delta);
+        }
+    }
+
+    pub(crate) fn pop_undo(&mut self) -> Option<Delta> {
+        self.undo.pop_back()
+    }
+
+    pub(crate) fn push_redo(&mut self, turns: VecDeque<Direction>) {
+        self.redo.push(turns);
+    }
+
+    pub(crate) fn pop_redo(&mut self) -> Option<VecDeque<Direction>> {
+        self.redo.pop()
+    }
+
+    pub(crate) fn clear_redo(&mut self) {
+        self.redo.clear();
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new(DEFAULT_HISTORY_DEPTH)
+    }
+}
+
+// like the body's hash, how a game got where it is doesn't make it a different game
+impl PartialEq for History {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
@@ -1,13 +1,26 @@
 use crate::direction::Direction;
+use crate::topology::{Board, Shape, Topology};
 
-#[derive(PartialEq, Debug,Copy,Clone)]
+#[derive(PartialEq, Eq, Hash, Debug,Copy,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InputType {
     Nothing,
     Up,
     Right,
     Down,
     Left,
+    // hex boards only
+    UpRight,
+    DownRight,
+    DownLeft,
+    UpLeft,
+    // relative to the way the snake is going, see resolve
+    TurnLeft,
+    TurnRight,
+    Straight,
     Quit,
+    // take back the last few ticks; the game leaves this to whoever is running it, see GameState::rewind
+    Rewind,
 }
 
 impl InputType {
@@ -17,7 +30,188 @@ impl InputType {
             Self::Right => Some(Direction::Right),
             Self::Down => Some(Direction::Down),
             Self::Left => Some(Direction::Left),
+            Self::UpRight => Some(Direction::UpRight),
+            Self::DownRight => Some(Direction::DownRight),
+            Self::DownLeft => Some(Direction::DownLeft),
+            Self::UpLeft => Some(Direction::UpLeft),
             _ => None,
         }
     }
+
+    /*
+     * The absolute input a relative one comes to when the snake is heading this way. A turn is one
+     * step round the board's directions, so a quarter turn on a square board and a sixth on a hex
+     * one. Going straight on is the same as doing nothing; it's there so a relative player has
+     * three moves. Anything else stays as it is.
+     * */
+    pub fn resolve(&self, heading: Direction, board: &Board) -> InputType {
+        match self {
+            Self::TurnLeft => InputType::from(board.turn(heading, false)),
+            Self::TurnRight => InputType::from(board.turn(heading, true)),
+            Self::Straight => InputType::Nothing,
+            _ => *self,
+        }
+    }
+}
+
+// how a player's left/right/up/down keys steer the snake
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Controls {
+    // each key goes that way on the screen
+    Absolute,
+    // left and right turn the snake, up carries on, so two keys are enough to play
+    Relative,
+}
+
+impl Controls {
+    // the same key on these controls
+    pub fn map(&self, input: InputType) -> InputType {
+        match (self, input) {
+            (Controls::Relative, InputType::Left) => InputType::TurnLeft,
+            (Controls::Relative, InputType::Right) => InputType::TurnRight,
+            (Controls::Relative, InputType::Up) => InputType::Straight,
+            (Controls::Relative, InputType::Down) => InputType::Nothing,
+            _ => input,
+        }
+    }
+
+    // every move a player on these controls can make on this shape of board
+    pub fn actions(&self, shape: Shape) -> &'static [InputType] {
+        match (self, shape) {
+            (Controls::Absolute, Shape::Square) => &[InputType::Up, InputType::Right, InputType::Down, InputType::Left],
+            (Controls::Absolute, Shape::Hex) => &[
+                InputType::UpRight,
+                InputType::Right,
+                InputType::DownRight,
+                InputType::DownLeft,
+                InputType::Left,
+                InputType::UpLeft,
+            ],
+            (Controls::Relative, _) => &[InputType::Straight, InputType::TurnLeft, InputType::TurnRight],
+        }
+    }
+}
+
+impl std::fmt::Display for Controls {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Controls::Absolute => write!(f, "absolute"),
+            Controls::Relative => write!(f, "relative"),
+        }
+    }
+}
+
+impl std::str::FromStr for Controls {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "absolute" => Ok(Controls::Absolute),
+            "relative" => Ok(Controls::Relative),
+            _ => Err(format!("unknown controls '{}' (expected absolute or relative)", s)),
+        }
+    }
+}
+
+impl From<Direction> for InputType {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Up => InputType::Up,
+            Direction::Right => InputType::Right,
+            Direction::Down => InputType::Down,
+            Direction::Left => InputType::Left,
+            Direction::UpRight => InputType::UpRight,
+            Direction::DownRight => InputType::DownRight,
+            Direction::DownLeft => InputType::DownLeft,
+            Direction::UpLeft => InputType::UpLeft,
+        }
+    }
+}
+
+impl std::fmt::Display for InputType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            InputType::Nothing => write!(f, "nothing"),
+            InputType::Up => write!(f, "up"),
+            InputType::Right => write!(f, "right"),
+            InputType::Down => write!(f, "down"),
+            InputType::Left => write!(f, "left"),
+            InputType::UpRight => write!(f, "up-right"),
+            InputType::DownRight => write!(f, "down-right"),
+            InputType::DownLeft => write!(f, "down-left"),
+            InputType::UpLeft => write!(f, "up-left"),
+            InputType::TurnLeft => write!(f, "turn-left"),
+            InputType::TurnRight => write!(f, "turn-right"),
+            InputType::Straight => write!(f, "straight"),
+            InputType::Quit => write!(f, "quit"),
+            InputType::Rewind => write!(f, "rewind"),
+        }
+    }
+}
+
+impl std::str::FromStr for InputType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nothing" => Ok(InputType::Nothing),
+            "up" => Ok(InputType::Up),
+            "right" => Ok(InputType::Right),
+            "down" => Ok(InputType::Down),
+            "left" => Ok(InputType::Left),
+            "up-right" => Ok(InputType::UpRight),
+            "down-right" => Ok(InputType::DownRight),
+            "down-left" => Ok(InputType::DownLeft),
+            "up-left" => Ok(InputType::UpLeft),
+            "turn-left" => Ok(InputType::TurnLeft),
+            "turn-right" => Ok(InputType::TurnRight),
+            "straight" => Ok(InputType::Straight),
+            "quit" => Ok(InputType::Quit),
+            "rewind" => Ok(InputType::Rewind),
+            _ => Err(format!("unknown input '{}'", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_turns_on_a_square_board() {
+        let board = Board::new(Shape::Square, 10, 8);
+        assert_eq!(InputType::TurnLeft.resolve(Direction::Up, &board), InputType::Left);
+        assert_eq!(InputType::TurnRight.resolve(Direction::Up, &board), InputType::Right);
+        assert_eq!(InputType::TurnLeft.resolve(Direction::Left, &board), InputType::Down);
+        assert_eq!(InputType::TurnRight.resolve(Direction::Left, &board), InputType::Up);
+        assert_eq!(InputType::Straight.resolve(Direction::Up, &board), InputType::Nothing);
+        assert_eq!(InputType::Down.resolve(Direction::Up, &board), InputType::Down);
+    }
+
+    #[test]
+    fn relative_turns_on_a_hex_board() {
+        let board = Board::new(Shape::Hex, 10, 8);
+        assert_eq!(InputType::TurnLeft.resolve(Direction::Right, &board), InputType::UpRight);
+        assert_eq!(InputType::TurnRight.resolve(Direction::Right, &board), InputType::DownRight);
+        assert_eq!(InputType::TurnLeft.resolve(Direction::UpRight, &board), InputType::UpLeft);
+        assert_eq!(InputType::TurnRight.resolve(Direction::UpLeft, &board), InputType::UpRight);
+        assert_eq!(InputType::Straight.resolve(Direction::Left, &board), InputType::Nothing);
+
+        // six turns the same way come back round
+        let mut heading = Direction::DownLeft;
+        for _ in 0..6 {
+            heading = InputType::TurnRight.resolve(heading, &board).get_direction().unwrap();
+        }
+        assert_eq!(heading, Direction::DownLeft);
+    }
+
+    #[test]
+    fn relative_controls_map_the_arrow_keys() {
+        assert_eq!(Controls::Relative.map(InputType::Left), InputType::TurnLeft);
+        assert_eq!(Controls::Relative.map(InputType::Right), InputType::TurnRight);
+        assert_eq!(Controls::Relative.map(InputType::Up), InputType::Straight);
+        assert_eq!(Controls::Relative.map(InputType::Down), InputType::Nothing);
+        assert_eq!(Controls::Relative.map(InputType::Quit), InputType::Quit);
+        assert_eq!(Controls::Absolute.map(InputType::Left), InputType::Left);
+    }
 }
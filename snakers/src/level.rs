@@ -0,0 +1,322 @@
+use crate::config::{make_coordinate_range, GameConfigBuilder};
+use crate::coord::Coord;
+use crate::direction::Direction;
+
+/*
+ * Plain text level maps. One character per cell, one line per row, every row the same width:
+ *
+ *   .  empty (a space works too)
+ *   #  wall
+ *   ^ > v <  the snake's head, pointing the way it starts out heading
+ *   o  the snake's body, in a straight line behind the head
+ *
+ * The board is as big as the text. If no body is drawn the snake gets the configured initial
+ * length. Characters not listed are reserved for more terrain later and are an error for now.
+ *
+ *   ##########
+ *   #........#
+ *   #..^..#..#
+ *   #..o..#..#
+ *   #..o.....#
+ *   ##########
+ * */
+
+const EMPTY: char = '.';
+const WALL: char = '#';
+const BODY: char = 'o';
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum LevelError {
+    Empty,
+    // row (0 based) that is not as wide as the first one
+    RaggedRow(usize),
+    UnknownCell { row: usize, col: usize, found: char },
+    NoSnake,
+    MultipleHeads,
+    // body cell that is not part of the line behind the head
+    StrayBody { row: usize, col: usize },
+}
+
+impl std::fmt::Display for LevelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LevelError::Empty => write!(f, "level has no cells"),
+            LevelError::RaggedRow(row) => write!(f, "row {} is not as wide as the first row", row + 1),
+            LevelError::UnknownCell { row, col, found } => {
+                write!(f, "unknown cell '{}' at row {}, column {}", found, row + 1, col + 1)
+            }
+            LevelError::NoSnake => write!(f, "level has no snake head"),
+            LevelError::MultipleHeads => write!(f, "level has more than one snake head"),
+            LevelError::StrayBody { row, col } => write!(
+                f,
+                "snake body at row {}, column {} is not behind the head",
+                row + 1,
+                col + 1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+// levels are square boards, so a snake heading along a hex diagonal is written as going up or down
+fn head_char(heading: Direction) -> char {
+    match heading {
+        Direction::Up | Direction::UpRight | Direction::UpLeft => '^',
+        Direction::Right => '>',
+        Direction::Down | Direction::DownRight | Direction::DownLeft => 'v',
+        Direction::Left => '<',
+    }
+}
+
+fn heading_from_char(c: char) -> Option<Direction> {
+    match c {
+        '^' => Some(Direction::Up),
+        '>' => Some(Direction::Right),
+        'v' => Some(Direction::Down),
+        '<' => Some(Direction::Left),
+        _ => None,
+    }
+}
+
+// everything in game coordinates, like GameConfig
+#[derive(PartialEq, Debug, Clone)]
+pub struct Level {
+    width: u32,
+    height: u32,
+    walls: Vec<Coord>,
+    spawn: Coord,
+    heading: Direction,
+    initial_length: Option<i32>,
+}
+
+impl Level {
+    pub fn new(width: u32,
+               height: u32,
+               walls: Vec<Coord>,
+               spawn: Coord,
+               heading: Direction,
+               initial_length: i32)
+        -> Self
+    {
+        Level {
+            width,
+            height,
+            walls,
+            spawn,
+            heading,
+            initial_length: Some(initial_length),
+        }
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_walls(&self) -> &[Coord] {
+        &self.walls
+    }
+
+    pub fn get_spawn(&self) -> Coord {
+        self.spawn
+    }
+
+    pub fn get_heading(&self) -> Direction {
+        self.heading
+    }
+
+    // set up the board and snake on top of whatever else the builder has
+    pub fn apply(&self, builder: GameConfigBuilder) -> GameConfigBuilder {
+        let builder = builder
+            .size(self.width, self.height)
+            .walls(self.walls.clone())
+            .spawn(self.spawn)
+            .heading(self.heading);
+
+        match self.initial_length {
+            Some(length) => builder.initial_length(length),
+            None => builder,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Level, LevelError> {
+        let mut rows: Vec<Vec<char>> = text
+            .lines()
+            .map(|line| line.trim_end_matches('\r').chars().collect())
+            .collect();
+
+        // a trailing newline (or a few) is not an extra row
+        while rows.last().is_some_and(|row| row.is_empty()) {
+            rows.pop();
+        }
+
+        if rows.is_empty() || rows[0].is_empty() {
+            return Err(LevelError::Empty);
+        }
+
+        let width = rows[0].len();
+        if let Some(row) = rows.iter().position(|r| r.len() != width) {
+            return Err(LevelError::RaggedRow(row));
+        }
+
+        let mut head: Option<(usize, usize, Direction)> = None;
+
+        for (row, cells) in rows.iter().enumerate() {
+            for (col, c) in cells.iter().enumerate() {
+                match *c {
+                    EMPTY | ' ' | WALL | BODY => (),
+                    c => match heading_from_char(c) {
+                        Some(heading) => {
+                            if head.is_some() {
+                                return Err(LevelError::MultipleHeads);
+                            }
+                            head = Some((row, col, heading));
+                        }
+                        None => return Err(LevelError::UnknownCell { row, col, found: c }),
+                    },
+                }
+            }
+        }
+
+        let (head_row, head_col, heading) = head.ok_or(LevelError::NoSnake)?;
+
+        // follow the body back from the head
+        let behind = heading.get_opposite().direction_get_unit_vector();
+        let mut body = Vec::new();
+        let (mut row, mut col) = (head_row as i32 + behind.y, head_col as i32 + behind.x);
+        while row >= 0
+            && col >= 0
+            && (row as usize) < rows.len()
+            && (col as usize) < width
+            && rows[row as usize][col as usize] == BODY
+        {
+            body.push((row as usize, col as usize));
+            row += behind.y;
+            col += behind.x;
+        }
+
+        let xrange = make_coordinate_range(width as u32);
+        let yrange = make_coordinate_range(rows.len() as u32);
+        let to_game = |row: usize, col: usize| Coord::new(col as i32 + xrange.0, row as i32 + yrange.0);
+
+        let mut walls = Vec::new();
+
+        for (row, cells) in rows.iter().enumerate() {
+            for (col, c) in cells.iter().enumerate() {
+                match *c {
+                    WALL => walls.push(to_game(row, col)),
+                    BODY if !body.contains(&(row, col)) => {
+                        return Err(LevelError::StrayBody { row, col })
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        Ok(Level {
+            width: width as u32,
+            height: rows.len() as u32,
+            walls,
+            spawn: to_game(head_row, head_col),
+            heading,
+            initial_length: if body.is_empty() { None } else { Some(body.len() as i32 + 1) },
+        })
+    }
+}
+
+impl std::str::FromStr for Level {
+    type Err = LevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Level::parse(s)
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let xrange = make_coordinate_range(self.width);
+        let yrange = make_coordinate_range(self.height);
+
+        let behind = self.heading.get_opposite().direction_get_unit_vector();
+        let body: Vec<Coord> = (1..self.initial_length.unwrap_or(1))
+            .map(|i| Coord::new(self.spawn.x + (behind.x * i), self.spawn.y + (behind.y * i)))
+            .collect();
+
+        for y in yrange.0..=yrange.1 {
+            for x in xrange.0..=xrange.1 {
+                let at = Coord::new(x, y);
+                let c = if at == self.spawn {
+                    head_char(self.heading)
+                } else if body.contains(&at) {
+                    BODY
+                } else if self.walls.contains(&at) {
+                    WALL
+                } else {
+                    EMPTY
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+
+    const LEVEL: &str = "\
+##########
+#........#
+#..^..#..#
+#..o..#..#
+#..o.....#
+##########
+";
+
+    #[test]
+    fn parse_and_display_round_trip() {
+        let level = Level::parse(LEVEL).unwrap();
+        assert_eq!((level.get_width(), level.get_height()), (10, 6));
+        assert_eq!((level.get_spawn(), level.get_heading()), (Coord::new(-2, -1), Direction::Up));
+        assert_eq!(level.get_walls().len(), 10 + 10 + 4 * 2 + 2);
+        assert_eq!(level.to_string(), LEVEL);
+        assert_eq!(level.to_string().parse::<Level>().unwrap(), level);
+
+        // and through a game and back
+        let config = level.apply(GameConfig::builder()).build().unwrap();
+        assert_eq!(config.get_initial_length(), 3);
+        assert_eq!(config.get_walls().len(), level.get_walls().len());
+    }
+
+    #[test]
+    fn no_body_keeps_the_configured_length() {
+        let level = Level::parse("....\r\n.>..\r\n....\r\n\n").unwrap();
+        assert_eq!(level.get_height(), 3);
+        let config = level.apply(GameConfig::builder().initial_length(2)).build().unwrap();
+        assert_eq!(config.get_initial_length(), 2);
+    }
+
+    #[test]
+    fn parse_rejects_bad_levels() {
+        let cases = [
+            ("", LevelError::Empty),
+            ("\n\n", LevelError::Empty),
+            ("....\n.>.\n", LevelError::RaggedRow(1)),
+            ("..x.\n.>..\n", LevelError::UnknownCell { row: 0, col: 2, found: 'x' }),
+            ("....\n.##.\n", LevelError::NoSnake),
+            (".>..\n.<..\n", LevelError::MultipleHeads),
+            ("o>..\n..o.\n", LevelError::StrayBody { row: 1, col: 2 }),
+        ];
+        for (text, error) in cases {
+            assert_eq!(Level::parse(text), Err(error), "{:?}", text);
+        }
+    }
+}
@@ -1,5 +1,25 @@
 pub mod game;
 pub mod direction;
 mod pair;
-mod coord;
+mod freecells;
+mod hash;
+pub mod astar;
+pub mod body;
+pub mod coord;
+pub mod config;
+pub mod controller;
+pub mod env;
+pub mod error;
+pub mod event;
+pub mod food;
+pub mod hamiltonian;
+pub mod history;
 pub mod inputtype;
+pub mod level;
+pub mod powerup;
+pub mod replay;
+pub mod speed;
+pub mod stats;
+pub mod topology;
+#[cfg(feature = "serde")]
+pub mod snapshot;
@@ -2,7 +2,8 @@ use std::ops::Sub;
 
 use crate::direction::Direction;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pair<T: Copy> {
     pub x: T,
     pub y: T,
@@ -15,10 +16,10 @@ where
     type Output = Pair<T>;
 
     fn sub(self, rhs: &Pair<T>) -> Pair<T> {
-        return Pair::<T> {
+        Pair::<T> {
             x: self.x - rhs.x,
             y: self.y - rhs.y,
-        };
+        }
     }
 }
 
@@ -53,7 +54,7 @@ where
             }
         }
 
-        return None;
+        None
     }
 }
 
@@ -0,0 +1,137 @@
+use rand::Rng;
+
+/*
+ * Pickups that give the snake a temporary effect. One can turn up on the board when food is
+ * eaten (PowerUpTable::chance percent of the time, and never more than one at once); running over
+ * it starts its effect, or restarts it if it is already going.
+ *
+ *   ghost         the snake can pass through its own body (walls still stop it)
+ *   slow-motion   ticks take twice as long (see GameState::tick_interval)
+ *   double-score  food is worth twice its value
+ *   magnet        food drifts a cell towards the head every tick
+ *
+ * An effect lasts for its duration in ticks, counting the one it was picked up on.
+ * */
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PowerUp {
+    Ghost,
+    SlowMotion,
+    DoubleScore,
+    Magnet,
+}
+
+impl PowerUp {
+    pub const ALL: [PowerUp; 4] = [PowerUp::Ghost, PowerUp::SlowMotion, PowerUp::DoubleScore, PowerUp::Magnet];
+
+    fn index(&self) -> usize {
+        match self {
+            PowerUp::Ghost => 0,
+            PowerUp::SlowMotion => 1,
+            PowerUp::DoubleScore => 2,
+            PowerUp::Magnet => 3,
+        }
+    }
+}
+
+impl std::fmt::Display for PowerUp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PowerUp::Ghost => write!(f, "ghost"),
+            PowerUp::SlowMotion => write!(f, "slow-motion"),
+            PowerUp::DoubleScore => write!(f, "double-score"),
+            PowerUp::Magnet => write!(f, "magnet"),
+        }
+    }
+}
+
+impl std::str::FromStr for PowerUp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ghost" => Ok(PowerUp::Ghost),
+            "slow-motion" => Ok(PowerUp::SlowMotion),
+            "double-score" => Ok(PowerUp::DoubleScore),
+            "magnet" => Ok(PowerUp::Magnet),
+            _ => Err(format!("unknown power-up '{}'", s)),
+        }
+    }
+}
+
+// an effect the snake has right now
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActiveEffect {
+    pub kind: PowerUp,
+    // including the current one
+    pub ticks_left: u32,
+}
+
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PowerUpRule {
+    // relative chance of being the one dropped, 0 means never
+    pub weight: u32,
+    // how many ticks the effect lasts
+    pub duration: u32,
+}
+
+// a PowerUpRule for every PowerUp, and how often one is dropped at all
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PowerUpTable {
+    rules: [PowerUpRule; 4],
+    // percent chance of dropping one each time food is eaten
+    chance: u32,
+}
+
+impl PowerUpTable {
+    pub fn get(&self, kind: PowerUp) -> PowerUpRule {
+        self.rules[kind.index()]
+    }
+
+    pub fn set(&mut self, kind: PowerUp, rule: PowerUpRule) {
+        self.rules[kind.index()] = rule;
+    }
+
+    pub fn get_chance(&self) -> u32 {
+        self.chance
+    }
+
+    pub fn set_chance(&mut self, chance: u32) {
+        self.chance = chance;
+    }
+
+    // decides whether to drop one and which, None for no power-up this time
+    pub(crate) fn pick<R: Rng>(&self, rng: &mut R) -> Option<PowerUp> {
+        let total: u64 = self.rules.iter().map(|rule| rule.weight as u64).sum();
+        if total == 0 || self.chance == 0 || rng.gen_range(0..100) >= self.chance {
+            return None;
+        }
+
+        let mut n = rng.gen_range(0..total);
+        for kind in PowerUp::ALL {
+            let weight = self.get(kind).weight as u64;
+            if n < weight {
+                return Some(kind);
+            }
+            n -= weight;
+        }
+        unreachable!()
+    }
+}
+
+impl Default for PowerUpTable {
+    fn default() -> Self {
+        PowerUpTable {
+            rules: [
+                PowerUpRule { weight: 1, duration: 20 },
+                PowerUpRule { weight: 1, duration: 30 },
+                PowerUpRule { weight: 1, duration: 40 },
+                PowerUpRule { weight: 1, duration: 25 },
+            ],
+            chance: 15,
+        }
+    }
+}
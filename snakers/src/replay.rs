@@ -0,0 +1,453 @@
+use crate::config::{ConfigError, GameConfig, WallPolicy};
+use crate::coord::Coord;
+use crate::direction::Direction;
+use crate::error::SnakersError;
+use crate::food::{FoodKind, FoodRule};
+use crate::game::{GameState, StateTransition};
+use crate::inputtype::InputType;
+use crate::powerup::{PowerUp, PowerUpRule};
+use crate::speed::SpeedCurve;
+use crate::stats::ScoreFormula;
+use crate::topology::Shape;
+
+/*
+ * A recorded game is its seed, its config and every input handed to GameState::handle_input,
+ * each stamped with the tick it arrived on (GameState::get_tick at the time). That is all it
+ * takes to play the game again: the rules are deterministic given the seed.
+ *
+ * Replay files are plain text, one record per line, fields separated by spaces:
+ *
+ *   snakers-replay 6              header and format version, always first
+ *   seed 8410567301               the RNG seed
+ *   size 24 15                    board width and height
+ *   shape square                  square or hex
+ *   initial-length 7
+ *   food regular 80 3 1           one line per food kind: weight, growth and value
+ *   power-up ghost 1 20           one line per power-up: weight and duration
+ *   power-up-chance 15            percent chance of a power-up when food is eaten
+ *   speed constant 500            the speed curve, as in speed.rs
+ *   spawn 0 0                     head position in game coordinates
+ *   heading up                    up, right, down or left, or a diagonal on a hex board
+ *   wall-policy die               die, wrap or bounce
+ *   score-formula 10 0 0          points per food, per tick and per unit of max length
+ *   turn-queue-depth 3            how many turns can wait for the next ticks
+ *   wall -3 4                     one line per wall cell, game coordinates
+ *   input 12 left                 tick, then the input as inputtype.rs writes it
+ *   end 57 9f3a4c0e12b7d655       final tick and GameState::state_hash in hex, always last
+ *
+ * Inputs are in the order they were handled. Blank lines and lines starting with '#' are
+ * ignored, so a replay can be annotated by hand.
+ * */
+
+const HEADER: &str = "snakers-replay";
+const FORMAT_VERSION: u32 = 6;
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum ReplayError {
+    Parse { line: usize, message: String },
+    Config(ConfigError),
+    // the replayed game did not end where the recorded one did
+    Mismatch {
+        expected_tick: u64,
+        expected_hash: u64,
+        tick: u64,
+        hash: u64,
+    },
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReplayError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            ReplayError::Config(e) => write!(f, "bad config: {}", e),
+            ReplayError::Mismatch { expected_tick, expected_hash, tick, hash } => write!(
+                f,
+                "replay ended on tick {} with hash {:016x}, recording ended on tick {} with hash {:016x}",
+                tick, hash, expected_tick, expected_hash
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<ConfigError> for ReplayError {
+    fn from(e: ConfigError) -> Self {
+        ReplayError::Config(e)
+    }
+}
+
+// collects inputs as a game is played; make it before the first tick
+pub struct Recorder {
+    seed: u64,
+    config: GameConfig,
+    inputs: Vec<(u64, InputType)>,
+}
+
+impl Recorder {
+    pub fn new(game: &GameState) -> Self {
+        debug_assert_eq!(game.get_tick(), 0, "recording has to start with the game");
+        Recorder {
+            seed: game.get_seed(),
+            config: game.get_config().clone(),
+            inputs: Vec::new(),
+        }
+    }
+
+    // call with each input just before it goes to game.handle_input
+    pub fn record(&mut self, game: &GameState, input: InputType) {
+        if input != InputType::Nothing {
+            self.inputs.push((game.get_tick(), input));
+        }
+    }
+
+    // call after game.rewind; the inputs for the ticks it took back go too
+    pub fn rewind(&mut self, game: &GameState) {
+        self.inputs.retain(|(tick, _)| *tick <= game.get_tick());
+    }
+
+    pub fn finish(self, game: &GameState) -> Replay {
+        Replay {
+            seed: self.seed,
+            config: self.config,
+            inputs: self.inputs,
+            final_tick: game.get_tick(),
+            final_hash: game.state_hash(),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Replay {
+    seed: u64,
+    config: GameConfig,
+    // sorted by tick, since they were recorded in order
+    inputs: Vec<(u64, InputType)>,
+    final_tick: u64,
+    final_hash: u64,
+}
+
+impl Replay {
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn get_config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn get_final_tick(&self) -> u64 {
+        self.final_tick
+    }
+
+    pub fn get_final_hash(&self) -> u64 {
+        self.final_hash
+    }
+
+    // a fresh game to play the inputs into
+    pub fn new_game(&self) -> Result<GameState, SnakersError> {
+        GameState::with_seed(self.config.clone(), self.seed)
+    }
+
+    // the inputs to hand to handle_input, in order, while the game is on this tick
+    pub fn inputs_at(&self, tick: u64) -> impl Iterator<Item = InputType> + '_ {
+        let start = self.inputs.partition_point(|(t, _)| *t < tick);
+        self.inputs[start..]
+            .iter()
+            .take_while(move |(t, _)| *t == tick)
+            .map(|(_, input)| *input)
+    }
+
+    /*
+     * run one tick of the replay: the inputs for the current tick, then the update, unless the
+     * recording stopped before updating. returns Stop when the replayed game is over either way.
+     * */
+    pub fn step(&self, game: &mut GameState) -> StateTransition {
+        for input in self.inputs_at(game.get_tick()) {
+            if let s @ StateTransition::Stop(_) = game.handle_input(input) {
+                return s;
+            }
+        }
+
+        if game.get_tick() >= self.final_tick {
+            return StateTransition::Stop(game.quit_outcome());
+        }
+
+        game.update_state()
+    }
+
+    // check a replayed game finished the same as the recording
+    pub fn verify(&self, game: &GameState) -> Result<(), ReplayError> {
+        let (tick, hash) = (game.get_tick(), game.state_hash());
+        if tick == self.final_tick && hash == self.final_hash {
+            Ok(())
+        } else {
+            Err(ReplayError::Mismatch {
+                expected_tick: self.final_tick,
+                expected_hash: self.final_hash,
+                tick,
+                hash,
+            })
+        }
+    }
+
+    // play the whole thing without a frontend and check where it ends up
+    pub fn play(&self) -> Result<GameState, SnakersError> {
+        let mut game = self.new_game()?;
+        while let StateTransition::Continue = self.step(&mut game) {}
+        self.verify(&game)?;
+        Ok(game)
+    }
+
+    pub fn parse(text: &str) -> Result<Replay, ReplayError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(n, line)| (n + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let error = |line: usize, message: String| ReplayError::Parse { line, message };
+
+        match lines.next() {
+            Some((n, line)) => {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                if fields.first() != Some(&HEADER) {
+                    return Err(error(n, "not a replay".to_string()));
+                }
+                if fields.get(1) != Some(&FORMAT_VERSION.to_string().as_str()) {
+                    return Err(error(n, format!("unsupported replay version '{}'", fields[1..].join(" "))));
+                }
+            }
+            None => return Err(error(0, "empty replay".to_string())),
+        }
+
+        let mut seed = None;
+        let mut builder = GameConfig::builder();
+        let mut walls = Vec::new();
+        let mut inputs = Vec::new();
+        let mut end = None;
+
+        for (n, line) in lines {
+            if end.is_some() {
+                return Err(error(n, "nothing can come after 'end'".to_string()));
+            }
+
+            let mut fields = line.split_whitespace();
+            let key = fields.next().unwrap(); // the line is not blank
+            let values: Vec<&str> = fields.collect();
+
+            let want = |count: usize| {
+                if values.len() == count {
+                    Ok(())
+                } else {
+                    Err(error(n, format!("'{}' takes {} value(s)", key, count)))
+                }
+            };
+
+            match key {
+                "seed" => {
+                    want(1)?;
+                    seed = Some(values[0].parse::<u64>().map_err(|_| error(n, format!("bad seed '{}'", values[0])))?);
+                }
+                "size" => {
+                    want(2)?;
+                    builder = builder.size(number(n, &values, 0)?, number(n, &values, 1)?);
+                }
+                "shape" => {
+                    want(1)?;
+                    builder = builder.shape(values[0].parse::<Shape>().map_err(|e| error(n, e))?);
+                }
+                "initial-length" => {
+                    want(1)?;
+                    builder = builder.initial_length(number(n, &values, 0)?);
+                }
+                "food" => {
+                    want(4)?;
+                    let kind = values[0].parse::<FoodKind>().map_err(|e| error(n, e))?;
+                    builder = builder.food(kind, FoodRule {
+                        weight: number(n, &values, 1)?,
+                        growth: number(n, &values, 2)?,
+                        value: number(n, &values, 3)?,
+                    });
+                }
+                "power-up" => {
+                    want(3)?;
+                    let kind = values[0].parse::<PowerUp>().map_err(|e| error(n, e))?;
+                    builder = builder.power_up(kind, PowerUpRule {
+                        weight: number(n, &values, 1)?,
+                        duration: number(n, &values, 2)?,
+                    });
+                }
+                "power-up-chance" => {
+                    want(1)?;
+                    builder = builder.power_up_chance(number(n, &values, 0)?);
+                }
+                "speed" => {
+                    builder = builder.speed(values.join(" ").parse::<SpeedCurve>().map_err(|e| error(n, e))?);
+                }
+                "spawn" => {
+                    want(2)?;
+                    builder = builder.spawn(Coord::new(number(n, &values, 0)?, number(n, &values, 1)?));
+                }
+                "heading" => {
+                    want(1)?;
+                    builder = builder.heading(values[0].parse::<Direction>().map_err(|e| error(n, e))?);
+                }
+                "wall-policy" => {
+                    want(1)?;
+                    builder = builder.wall_policy(values[0].parse::<WallPolicy>().map_err(|e| error(n, e))?);
+                }
+                "score-formula" => {
+                    want(3)?;
+                    builder = builder.score_formula(ScoreFormula {
+                        per_food: number(n, &values, 0)?,
+                        per_tick: number(n, &values, 1)?,
+                        per_length: number(n, &values, 2)?,
+                    });
+                }
+                "turn-queue-depth" => {
+                    want(1)?;
+                    builder = builder.turn_queue_depth(number(n, &values, 0)?);
+                }
+                "wall" => {
+                    want(2)?;
+                    walls.push(Coord::new(number(n, &values, 0)?, number(n, &values, 1)?));
+                }
+                "input" => {
+                    want(2)?;
+                    let tick = number(n, &values, 0)?;
+                    if inputs.last().is_some_and(|(last, _)| *last > tick) {
+                        return Err(error(n, "inputs are out of order".to_string()));
+                    }
+                    inputs.push((tick, values[1].parse::<InputType>().map_err(|e| error(n, e))?));
+                }
+                "end" => {
+                    want(2)?;
+                    let hash = u64::from_str_radix(values[1], 16)
+                        .map_err(|_| error(n, format!("bad hash '{}'", values[1])))?;
+                    end = Some((number(n, &values, 0)?, hash));
+                }
+                _ => return Err(error(n, format!("unknown record '{}'", key))),
+            }
+        }
+
+        let seed = seed.ok_or_else(|| error(0, "no seed".to_string()))?;
+        let (final_tick, final_hash) = end.ok_or_else(|| error(0, "no end".to_string()))?;
+
+        Ok(Replay {
+            seed,
+            config: builder.walls(walls).build()?,
+            inputs,
+            final_tick,
+            final_hash,
+        })
+    }
+}
+
+// a number for a field, parsed as that field's type so it has to fit rather than wrap round into it
+fn number<T: std::str::FromStr>(line: usize, values: &[&str], i: usize) -> Result<T, ReplayError> {
+    values[i].parse::<T>().map_err(|_| ReplayError::Parse {
+        line,
+        message: format!("'{}' is not a number, or is out of range", values[i]),
+    })
+}
+
+impl std::str::FromStr for Replay {
+    type Err = ReplayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Replay::parse(s)
+    }
+}
+
+impl std::fmt::Display for Replay {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let config = &self.config;
+        let score = config.get_score_formula();
+
+        writeln!(f, "{} {}", HEADER, FORMAT_VERSION)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "size {} {}", config.get_width(), config.get_height())?;
+        writeln!(f, "shape {}", config.get_shape())?;
+        writeln!(f, "initial-length {}", config.get_initial_length())?;
+        for kind in FoodKind::ALL {
+            let rule = config.get_food().get(kind);
+            writeln!(f, "food {} {} {} {}", kind, rule.weight, rule.growth, rule.value)?;
+        }
+        for kind in PowerUp::ALL {
+            let rule = config.get_power_ups().get(kind);
+            writeln!(f, "power-up {} {} {}", kind, rule.weight, rule.duration)?;
+        }
+        writeln!(f, "power-up-chance {}", config.get_power_ups().get_chance())?;
+        writeln!(f, "speed {}", config.get_speed())?;
+        writeln!(f, "spawn {} {}", config.get_spawn().x, config.get_spawn().y)?;
+        writeln!(f, "heading {}", config.get_heading())?;
+        writeln!(f, "wall-policy {}", config.get_wall_policy())?;
+        writeln!(f, "score-formula {} {} {}", score.per_food, score.per_tick, score.per_length)?;
+        writeln!(f, "turn-queue-depth {}", config.get_turn_queue_depth())?;
+        for wall in config.get_walls() {
+            writeln!(f, "wall {} {}", wall.x, wall.y)?;
+        }
+        for (tick, input) in self.inputs.iter() {
+            writeln!(f, "input {} {}", tick, input)?;
+        }
+        writeln!(f, "end {} {:016x}", self.final_tick, self.final_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text() -> String {
+        let config = GameConfig::builder().size(10, 8).build().unwrap();
+        let mut game = GameState::with_seed(config, 3).unwrap();
+        let mut recorder = Recorder::new(&game);
+        for input in [InputType::Right, InputType::Nothing, InputType::Down] {
+            recorder.record(&game, input);
+            game.handle_input(input);
+            game.update_state();
+        }
+        recorder.finish(&game).to_string()
+    }
+
+    // the line in the text that starts with this record, counting from 1
+    fn line_of(text: &str, key: &str) -> usize {
+        text.lines().position(|line| line.starts_with(key)).unwrap() + 1
+    }
+
+    #[test]
+    fn round_trip() {
+        let text = text();
+        assert_eq!(Replay::parse(&text).unwrap().to_string(), text);
+    }
+
+    #[test]
+    fn biggest_numbers_round_trip() {
+        let huge = FoodRule { weight: 1, growth: 1, value: u64::MAX };
+        let config = GameConfig::builder()
+            .size(10, 8)
+            .food(FoodKind::Regular, huge)
+            .score_formula(ScoreFormula { per_food: u64::MAX, per_tick: u64::MAX, per_length: u64::MAX })
+            .build()
+            .unwrap();
+        let game = GameState::with_seed(config, u64::MAX).unwrap();
+        let text = Recorder::new(&game).finish(&game).to_string();
+        let replay = Replay::parse(&text).unwrap();
+        assert_eq!(replay.to_string(), text);
+        assert_eq!((replay.get_seed(), replay.get_config()), (u64::MAX, game.get_config()));
+    }
+
+    #[test]
+    fn numbers_out_of_range() {
+        let text = text();
+        for (key, bad) in [("size ", "size -1 -1"), ("size ", "size 4294967296 8"), ("turn-queue-depth", "turn-queue-depth -3")] {
+            let line = line_of(&text, key);
+            let edited: Vec<&str> = text.lines().map(|l| if l.starts_with(key) { bad } else { l }).collect();
+            match Replay::parse(&edited.join("\n")) {
+                Err(ReplayError::Parse { line: at, .. }) => assert_eq!(at, line, "{}", bad),
+                other => panic!("{} parsed as {:?}", bad, other),
+            }
+        }
+    }
+}
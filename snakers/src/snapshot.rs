@@ -0,0 +1,202 @@
+use crate::error::SnakersError;
+use crate::game::GameState;
+
+/*
+ * Save and load a running game. Everything in GameState goes in, including the RNG state and any
+ * input waiting for the next tick, so a loaded game carries on exactly as the saved one would
+ * have. The one thing left out is the history for rewinding, which starts over on loading.
+ *
+ * Two encodings of the same data:
+ *
+ *   JSON    {"version": 1, "state": { ... }}, for reading and for attaching to bug reports
+ *   binary  the magic bytes "SNKR", the version as a little endian u32, then the state encoded
+ *           with bincode
+ *
+ * SNAPSHOT_VERSION goes up whenever GameState changes shape. Loading a snapshot with any other
+ * version is an error rather than a guess, and so is a state that fails GameState::validate.
+ * */
+
+pub const SNAPSHOT_VERSION: u32 = 9;
+
+const MAGIC: &[u8; 4] = b"SNKR";
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Json(serde_json::Error),
+    Binary(bincode::Error),
+    BadMagic,
+    UnsupportedVersion(u32),
+    Invalid(SnakersError),
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SnapshotError::Json(e) => write!(f, "bad json snapshot: {}", e),
+            SnapshotError::Binary(e) => write!(f, "bad binary snapshot: {}", e),
+            SnapshotError::BadMagic => write!(f, "not a snapshot"),
+            SnapshotError::UnsupportedVersion(v) => write!(
+                f,
+                "snapshot version {} is not supported (expected {})",
+                v, SNAPSHOT_VERSION
+            ),
+            SnapshotError::Invalid(e) => write!(f, "snapshot is not a playable game: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(e: serde_json::Error) -> Self {
+        SnapshotError::Json(e)
+    }
+}
+
+impl From<SnakersError> for SnapshotError {
+    fn from(e: SnakersError) -> Self {
+        SnapshotError::Invalid(e)
+    }
+}
+
+impl From<bincode::Error> for SnapshotError {
+    fn from(e: bincode::Error) -> Self {
+        SnapshotError::Binary(e)
+    }
+}
+
+#[derive(serde::Serialize)]
+struct SnapshotOut<'a> {
+    version: u32,
+    state: &'a GameState,
+}
+
+#[derive(serde::Deserialize)]
+struct SnapshotIn {
+    version: u32,
+    state: serde_json::Value,
+}
+
+impl GameState {
+    pub fn to_json(&self) -> Result<String, SnapshotError> {
+        Ok(serde_json::to_string(&SnapshotOut {
+            version: SNAPSHOT_VERSION,
+            state: self,
+        })?)
+    }
+
+    pub fn from_json(json: &str) -> Result<GameState, SnapshotError> {
+        // check the version before trying to make sense of the state
+        let snapshot: SnapshotIn = serde_json::from_str(json)?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(snapshot.version));
+        }
+        let game: GameState = serde_json::from_value(snapshot.state)?;
+        game.validate()?;
+        Ok(game)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        bincode::serialize_into(&mut bytes, self)?;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<GameState, SnapshotError> {
+        if bytes.len() < 8 || &bytes[0..4] != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+
+        let version = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let game: GameState = bincode::deserialize(&bytes[8..])?;
+        game.validate()?;
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+
+    fn game() -> GameState {
+        let config = GameConfig::builder().size(10, 8).build().unwrap();
+        GameState::with_seed(config, 7).unwrap()
+    }
+
+    // the game as a snapshot, changed by `edit` on the way through
+    fn edited(edit: impl FnOnce(&mut serde_json::Value)) -> Result<GameState, SnapshotError> {
+        let mut json: serde_json::Value = serde_json::from_str(&game().to_json().unwrap()).unwrap();
+        edit(&mut json["state"]);
+        GameState::from_json(&json.to_string())
+    }
+
+    #[test]
+    fn round_trip() {
+        let game = game();
+        assert_eq!(GameState::from_json(&game.to_json().unwrap()).unwrap(), game);
+        assert_eq!(GameState::from_bytes(&game.to_bytes().unwrap()).unwrap(), game);
+    }
+
+    #[test]
+    fn free_cell_index_is_not_saved() {
+        let json: serde_json::Value = serde_json::from_str(&game().to_json().unwrap()).unwrap();
+        assert!(json["state"]["free"].get("slots").is_none());
+    }
+
+    // a snapshot that deserializes fine but isn't a game that can be played on
+    fn assert_invalid(edit: impl FnOnce(&mut serde_json::Value)) {
+        match edited(edit) {
+            Err(SnapshotError::Invalid(_)) => (),
+            other => panic!("expected an invalid state, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn a_saved_free_cell_index_is_ignored() {
+        let mut game = edited(|state| state["free"]["slots"] = serde_json::json!(vec![999; 80])).unwrap();
+        game.update_state();
+        game.validate().unwrap();
+    }
+
+    #[test]
+    fn corrupted_states_are_rejected() {
+        // a free cell where the snake is, in place of one that is free
+        let snake = game().get_snake().get_body().front().unwrap().coord;
+        let g = game().game_to_grid(&snake);
+        assert_invalid(|state| state["free"]["cells"][0] = serde_json::json!({"x": g.x, "y": g.y}));
+
+        // a cell missing from the free list
+        assert_invalid(|state| {
+            state["free"]["cells"].as_array_mut().unwrap().pop();
+        });
+
+        // more turns than the queue holds, or ones the board doesn't have
+        assert_invalid(|state| state["turns"] = serde_json::json!(["Left", "Up", "Left", "Up"]));
+        assert_invalid(|state| state["turns"] = serde_json::json!(["UpRight"]));
+        assert_invalid(|state| state["snake"]["body"]["segments"][0]["dir_next"] = serde_json::json!("DownLeft"));
+
+        // a power-up that isn't there, or is off the board
+        assert_invalid(|state| state["power_up"] = serde_json::json!({"x": 0, "y": 0}));
+        assert_invalid(|state| state["power_up"] = serde_json::json!({"x": 99, "y": 99}));
+
+        // an effect that should already have ended
+        assert_invalid(|state| state["snake"]["effects"] = serde_json::json!([{"kind": "Ghost", "ticks_left": 0}]));
+        assert!(edited(|state| state["snake"]["effects"] = serde_json::json!([{"kind": "Ghost", "ticks_left": 1}])).is_ok());
+    }
+
+    #[test]
+    fn bad_free_cells_are_rejected() {
+        assert!(edited(|state| state["free"]["cells"][0] = serde_json::json!({"x": 999, "y": 0})).is_err());
+        assert!(edited(|state| state["free"]["cells"][0] = serde_json::json!({"x": -1, "y": 0})).is_err());
+        assert!(edited(|state| {
+            let first = state["free"]["cells"][0].clone();
+            state["free"]["cells"][1] = first;
+        }).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use std::time::Duration;
+
+use crate::stats::GameStats;
+
+/*
+ * How long a tick should take, as the game goes on. The core doesn't keep time itself; frontends
+ * ask GameState::tick_interval before each tick so the game speeds up the same way in all of them.
+ *
+ *   constant MS                                   the same interval all game
+ *   step-per-food START STEP EVERY MIN            STEP ms faster for every EVERY food eaten
+ *   exponential-by-score START PERCENT POINTS MIN PERCENT % faster for every POINTS of score
+ *
+ * All times are in milliseconds; the interval never drops below MIN. The text above is also what
+ * Display writes and FromStr reads.
+ * */
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpeedCurve {
+    Constant {
+        interval_ms: u64,
+    },
+    StepPerFood {
+        start_ms: u64,
+        step_ms: u64,
+        every: u64,
+        min_ms: u64,
+    },
+    ExponentialByScore {
+        start_ms: u64,
+        percent: u64,
+        points: u64,
+        min_ms: u64,
+    },
+}
+
+impl SpeedCurve {
+    pub fn interval(&self, stats: &GameStats) -> Duration {
+        let ms = match *self {
+            SpeedCurve::Constant { interval_ms } => interval_ms,
+            SpeedCurve::StepPerFood { start_ms, step_ms, every, min_ms } => {
+                let steps = stats.get_food_eaten() / every;
+                start_ms.saturating_sub(steps.saturating_mul(step_ms)).max(min_ms)
+            }
+            SpeedCurve::ExponentialByScore { start_ms, percent, points, min_ms } => {
+                let steps = (stats.get_score() / points).min(i32::MAX as u64) as i32;
+                let scale = (1.0 - (percent.min(100) as f64 / 100.0)).powi(steps);
+                ((start_ms as f64 * scale) as u64).max(min_ms)
+            }
+        };
+        Duration::from_millis(ms)
+    }
+
+    // every interval and divisor has to be above 0
+    pub(crate) fn is_valid(&self) -> bool {
+        match *self {
+            SpeedCurve::Constant { interval_ms } => interval_ms > 0,
+            SpeedCurve::StepPerFood { start_ms, every, min_ms, .. } => {
+                start_ms > 0 && every > 0 && min_ms > 0
+            }
+            SpeedCurve::ExponentialByScore { start_ms, points, min_ms, .. } => {
+                start_ms > 0 && points > 0 && min_ms > 0
+            }
+        }
+    }
+}
+
+impl Default for SpeedCurve {
+    fn default() -> Self {
+        SpeedCurve::StepPerFood {
+            start_ms: 500,
+            step_ms: 20,
+            every: 1,
+            min_ms: 100,
+        }
+    }
+}
+
+impl std::fmt::Display for SpeedCurve {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SpeedCurve::Constant { interval_ms } => write!(f, "constant {}", interval_ms),
+            SpeedCurve::StepPerFood { start_ms, step_ms, every, min_ms } => {
+                write!(f, "step-per-food {} {} {} {}", start_ms, step_ms, every, min_ms)
+            }
+            SpeedCurve::ExponentialByScore { start_ms, percent, points, min_ms } => write!(
+                f,
+                "exponential-by-score {} {} {} {}",
+                start_ms, percent, points, min_ms
+            ),
+        }
+    }
+}
+
+impl std::str::FromStr for SpeedCurve {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+
+        let mut values = Vec::new();
+        for field in fields.iter().skip(1) {
+            values.push(field.parse::<u64>().map_err(|_| format!("'{}' is not a number", field))?);
+        }
+
+        let want = |count: usize| match values.len() == count {
+            true => Ok(()),
+            false => Err(format!("speed curve '{}' takes {} value(s)", fields[0], count)),
+        };
+
+        match fields.first() {
+            Some(&"constant") => {
+                want(1)?;
+                Ok(SpeedCurve::Constant { interval_ms: values[0] })
+            }
+            Some(&"step-per-food") => {
+                want(4)?;
+                Ok(SpeedCurve::StepPerFood {
+                    start_ms: values[0],
+                    step_ms: values[1],
+                    every: values[2],
+                    min_ms: values[3],
+                })
+            }
+            Some(&"exponential-by-score") => {
+                want(4)?;
+                Ok(SpeedCurve::ExponentialByScore {
+                    start_ms: values[0],
+                    percent: values[1],
+                    points: values[2],
+                    min_ms: values[3],
+                })
+            }
+            _ => Err(format!(
+                "unknown speed curve '{}' (expected constant, step-per-food or exponential-by-score)",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::food::FoodRule;
+    use crate::stats::ScoreFormula;
+
+    #[test]
+    fn parse_and_display_round_trip() {
+        let step = SpeedCurve::StepPerFood { start_ms: 500, step_ms: 20, every: 2, min_ms: 100 };
+        let exponential = SpeedCurve::ExponentialByScore { start_ms: 400, percent: 10, points: 50, min_ms: 80 };
+        let curves = [
+            ("constant 150", SpeedCurve::Constant { interval_ms: 150 }),
+            ("step-per-food 500 20 2 100", step),
+            ("exponential-by-score 400 10 50 80", exponential),
+        ];
+        for (text, curve) in curves {
+            assert_eq!(text.parse::<SpeedCurve>(), Ok(curve));
+            assert_eq!(curve.to_string(), text);
+        }
+        assert_eq!("  constant   150 ".parse::<SpeedCurve>(), Ok(SpeedCurve::Constant { interval_ms: 150 }));
+    }
+
+    #[test]
+    fn parse_rejects_bad_curves() {
+        let wrong = ["", "linear 100", "constant", "constant 100 200", "step-per-food 500 20 1"];
+        for text in wrong.into_iter().chain(["constant -5", "constant fast"]) {
+            assert!(text.parse::<SpeedCurve>().is_err(), "{:?}", text);
+        }
+    }
+
+    // stats after eating this much food worth 1 each, scored 10 a food
+    fn fed(food: u64) -> GameStats {
+        let mut stats = GameStats::new(3);
+        for _ in 0..food {
+            stats.record_move(3, false, Some(FoodRule { weight: 1, growth: 0, value: 1 }), &ScoreFormula::default());
+        }
+        stats
+    }
+
+    #[test]
+    fn intervals_speed_up_to_the_minimum() {
+        let step = SpeedCurve::StepPerFood { start_ms: 500, step_ms: 20, every: 2, min_ms: 100 };
+        let ms = |curve: SpeedCurve, food| curve.interval(&fed(food)).as_millis();
+        assert_eq!([ms(step, 0), ms(step, 1), ms(step, 2), ms(step, 5), ms(step, 1000)], [500, 500, 480, 460, 100]);
+
+        // 10% faster for every 50 points, which is 5 food
+        let exponential = SpeedCurve::ExponentialByScore { start_ms: 400, percent: 10, points: 50, min_ms: 80 };
+        let ms = |food| exponential.interval(&fed(food)).as_millis();
+        assert_eq!([ms(0), ms(4), ms(5), ms(10), ms(1000)], [400, 400, 360, 324, 80]);
+
+        assert_eq!(SpeedCurve::Constant { interval_ms: 150 }.interval(&fed(1000)).as_millis(), 150);
+        assert!(!SpeedCurve::StepPerFood { start_ms: 500, step_ms: 20, every: 0, min_ms: 100 }.is_valid());
+        assert!(SpeedCurve::default().is_valid());
+    }
+}
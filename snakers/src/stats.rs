@@ -0,0 +1,117 @@
+use crate::food::FoodRule;
+
+/*
+ * Points are a weighted sum of what the snake has done so far. Food counts by its value rather
+ * than by how many were eaten (regular food is worth 1, see food.rs). The default only counts
+ * food, so the score is ten points per regular food eaten.
+ * */
+#[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoreFormula {
+    pub per_food: u64,
+    pub per_tick: u64,
+    pub per_length: u64,
+}
+
+impl ScoreFormula {
+    // stops at u64::MAX rather than overflowing, for formulas with huge weights
+    pub fn score(&self, stats: &GameStats) -> u64 {
+        (self.per_food.saturating_mul(stats.food_value))
+            .saturating_add(self.per_tick.saturating_mul(stats.ticks))
+            .saturating_add(self.per_length.saturating_mul(stats.max_length as u64))
+    }
+}
+
+impl Default for ScoreFormula {
+    fn default() -> Self {
+        ScoreFormula {
+            per_food: 10,
+            per_tick: 0,
+            per_length: 0,
+        }
+    }
+}
+
+// running totals for a game, kept up to date by GameState on every tick
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameStats {
+    // ticks the snake has moved without dying
+    ticks: u64,
+    food_eaten: u64,
+    // sum of FoodRule::value over the food eaten
+    food_value: u64,
+    max_length: usize,
+    // moves where the snake went a different way than on the move before
+    turns: u64,
+    score: u64,
+}
+
+impl GameStats {
+    pub(crate) fn new(length: usize) -> Self {
+        GameStats {
+            max_length: length,
+            ..Default::default()
+        }
+    }
+
+    pub fn get_ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn get_food_eaten(&self) -> u64 {
+        self.food_eaten
+    }
+
+    pub fn get_food_value(&self) -> u64 {
+        self.food_value
+    }
+
+    pub fn get_max_length(&self) -> usize {
+        self.max_length
+    }
+
+    pub fn get_turns(&self) -> u64 {
+        self.turns
+    }
+
+    pub fn get_score(&self) -> u64 {
+        self.score
+    }
+
+    pub(crate) fn record_move(&mut self, length: usize, turned: bool, eaten: Option<FoodRule>, formula: &ScoreFormula) {
+        self.ticks += 1;
+        self.max_length = self.max_length.max(length);
+        if turned {
+            self.turns += 1;
+        }
+        if let Some(food) = eaten {
+            self.food_eaten += 1;
+            self.food_value = self.food_value.saturating_add(food.value);
+        }
+        self.score = formula.score(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huge_formula_saturates() {
+        let formula = ScoreFormula { per_food: u64::MAX, per_tick: u64::MAX, per_length: 1 };
+        let mut stats = GameStats::new(3);
+        stats.record_move(3, false, None, &formula);
+        assert_eq!(stats.get_score(), u64::MAX);
+
+        let food = FoodRule { weight: 1, growth: 1, value: u64::MAX };
+        stats.record_move(4, true, Some(food), &formula);
+        stats.record_move(5, false, Some(food), &formula);
+        assert_eq!((stats.get_food_value(), stats.get_score()), (u64::MAX, u64::MAX));
+
+        // and the default still counts ten a food
+        let mut stats = GameStats::new(3);
+        stats.record_move(4, false, Some(FoodRule { weight: 1, growth: 1, value: 1 }), &ScoreFormula::default());
+        assert_eq!(stats.get_score(), 10);
+    }
+}
@@ -0,0 +1,377 @@
+use crate::config::make_coordinate_range;
+use crate::coord::Coord;
+use crate::direction::Direction;
+
+/*
+ * The shape of the board: which ways the snake can go, which cell is next to which, where the
+ * edges are and how game coordinates map onto the grid. Boards are always a rectangle of cells
+ * stored row by row with (0, 0) in the middle, as described in config.rs; what changes between
+ * topologies is how the cells connect.
+ *
+ * Only directions() and neighbor() differ from board to board, everything else comes from the
+ * coordinate ranges.
+ * */
+pub trait Topology {
+    // the ways the snake can go, clockwise
+    fn directions(&self) -> &'static [Direction];
+
+    // the next cell that way, which can be off the board. a direction this board doesn't have
+    // goes the way its unit vector points.
+    fn neighbor(&self, at: &Coord, direction: Direction) -> Coord;
+
+    // fewest steps between two cells if nothing were in the way, across the edges if they wrap
+    fn distance(&self, from: &Coord, to: &Coord, wrap: bool) -> u32;
+
+    fn get_xrange(&self) -> (i32, i32);
+    fn get_yrange(&self) -> (i32, i32);
+
+    fn get_width(&self) -> u32 {
+        (self.get_xrange().1 - self.get_xrange().0 + 1) as u32
+    }
+
+    fn get_height(&self) -> u32 {
+        (self.get_yrange().1 - self.get_yrange().0 + 1) as u32
+    }
+
+    fn has_direction(&self, direction: Direction) -> bool {
+        self.directions().contains(&direction)
+    }
+
+    // the next direction round, one way or the other; ones this board doesn't have stay as they are
+    fn turn(&self, direction: Direction, clockwise: bool) -> Direction {
+        let ring = self.directions();
+        match ring.iter().position(|d| *d == direction) {
+            Some(i) if clockwise => ring[(i + 1) % ring.len()],
+            Some(i) => ring[(i + ring.len() - 1) % ring.len()],
+            None => direction,
+        }
+    }
+
+    fn in_bounds(&self, at: &Coord) -> bool {
+        let (xrange, yrange) = (self.get_xrange(), self.get_yrange());
+        at.x >= xrange.0 && at.x <= xrange.1 && at.y >= yrange.0 && at.y <= yrange.1
+    }
+
+    // bring a coordinate that has gone off one edge back in from the opposite edge
+    fn wrap(&self, at: &Coord) -> Coord {
+        let (xrange, yrange) = (self.get_xrange(), self.get_yrange());
+        Coord {
+            x: (at.x - xrange.0).rem_euclid(self.get_width() as i32) + xrange.0,
+            y: (at.y - yrange.0).rem_euclid(self.get_height() as i32) + yrange.0,
+        }
+    }
+
+    fn to_grid(&self, at: &Coord) -> Coord {
+        Coord::new(at.x - self.get_xrange().0, at.y - self.get_yrange().0)
+    }
+
+    fn to_game(&self, at: &Coord) -> Coord {
+        Coord::new(at.x + self.get_xrange().0, at.y + self.get_yrange().0)
+    }
+}
+
+// which topology a config asks for
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Shape {
+    Square,
+    Hex,
+}
+
+impl Shape {
+    // the way a snake starts out when the config doesn't say
+    pub fn default_heading(&self) -> Direction {
+        match self {
+            Shape::Square => Direction::Up,
+            Shape::Hex => Direction::Right,
+        }
+    }
+}
+
+impl std::fmt::Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Shape::Square => write!(f, "square"),
+            Shape::Hex => write!(f, "hex"),
+        }
+    }
+}
+
+impl std::str::FromStr for Shape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "square" => Ok(Shape::Square),
+            "hex" => Ok(Shape::Hex),
+            _ => Err(format!("unknown board shape '{}' (expected square or hex)", s)),
+        }
+    }
+}
+
+// the usual board: four ways to go, cells side by side in rows and columns
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SquareBoard {
+    xrange: (i32, i32),
+    yrange: (i32, i32),
+}
+
+impl SquareBoard {
+    pub fn new(width: u32, height: u32) -> Self {
+        SquareBoard {
+            xrange: make_coordinate_range(width),
+            yrange: make_coordinate_range(height),
+        }
+    }
+}
+
+impl Topology for SquareBoard {
+    fn directions(&self) -> &'static [Direction] {
+        &Direction::SQUARE
+    }
+
+    fn neighbor(&self, at: &Coord, direction: Direction) -> Coord {
+        at.calculate_neighbor(direction)
+    }
+
+    fn distance(&self, from: &Coord, to: &Coord, wrap: bool) -> u32 {
+        let (mut dx, mut dy) = ((to.x - from.x).unsigned_abs(), (to.y - from.y).unsigned_abs());
+        // going the other way round might be shorter
+        if wrap {
+            dx = dx.min(self.get_width() - dx);
+            dy = dy.min(self.get_height() - dy);
+        }
+        dx + dy
+    }
+
+    fn get_xrange(&self) -> (i32, i32) {
+        self.xrange
+    }
+
+    fn get_yrange(&self) -> (i32, i32) {
+        self.yrange
+    }
+}
+
+/*
+ * Six ways to go, with pointy-topped hexagons laid out in offset rows: every other row (the odd
+ * ones, counting grid rows from 0 at the top) is pushed half a cell to the right. Left and right
+ * stay in the row, the four diagonals go to the row above or below, and which two cells those are
+ * depends on whether the row is pushed over.
+ *
+ *    0 1 2 3          UpLeft and UpRight of cell 1 in row 1 are cells 1 and 2 in row 0;
+ *     0 1 2 3         of cell 1 in row 2 they are cells 0 and 1 in row 1.
+ *    0 1 2 3
+ *
+ * Wrapping top to bottom only lines up when the number of rows is even, which the config checks.
+ * */
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexBoard {
+    xrange: (i32, i32),
+    yrange: (i32, i32),
+}
+
+impl HexBoard {
+    pub fn new(width: u32, height: u32) -> Self {
+        HexBoard {
+            xrange: make_coordinate_range(width),
+            yrange: make_coordinate_range(height),
+        }
+    }
+
+    // whether this cell's row is pushed half a cell to the right
+    pub fn is_shifted(&self, at: &Coord) -> bool {
+        (at.y - self.yrange.0).rem_euclid(2) == 1
+    }
+
+    // cube coordinates (x and z; y is -x-z), where hex distances are easy
+    fn cube(&self, at: &Coord) -> (i32, i32) {
+        let g = self.to_grid(at);
+        (g.x - (g.y - (g.y & 1)) / 2, g.y)
+    }
+}
+
+impl Topology for HexBoard {
+    fn directions(&self) -> &'static [Direction] {
+        &Direction::HEX
+    }
+
+    fn neighbor(&self, at: &Coord, direction: Direction) -> Coord {
+        // the diagonals lean one more cell to the right from a shifted row
+        let shift = if self.is_shifted(at) { 1 } else { 0 };
+        match direction {
+            Direction::UpRight => Coord::new(at.x + shift, at.y - 1),
+            Direction::DownRight => Coord::new(at.x + shift, at.y + 1),
+            Direction::DownLeft => Coord::new(at.x + shift - 1, at.y + 1),
+            Direction::UpLeft => Coord::new(at.x + shift - 1, at.y - 1),
+            _ => at.calculate_neighbor(direction),
+        }
+    }
+
+    fn distance(&self, from: &Coord, to: &Coord, wrap: bool) -> u32 {
+        let (fx, fz) = self.cube(from);
+
+        // across an edge is the same cell a board's width or height away
+        let (width, height) = (self.get_width() as i32, self.get_height() as i32);
+        let shifts: &[i32] = if wrap { &[-1, 0, 1] } else { &[0] };
+
+        let mut best = u32::MAX;
+        for sy in shifts {
+            for sx in shifts {
+                let (tx, tz) = self.cube(&Coord::new(to.x + sx * width, to.y + sy * height));
+                let (dx, dz) = (tx - fx, tz - fz);
+                let distance = (dx.unsigned_abs() + dz.unsigned_abs() + (dx + dz).unsigned_abs()) / 2;
+                best = best.min(distance);
+            }
+        }
+        best
+    }
+
+    fn get_xrange(&self) -> (i32, i32) {
+        self.xrange
+    }
+
+    fn get_yrange(&self) -> (i32, i32) {
+        self.yrange
+    }
+}
+
+// any of the boards above, so a GameState can hold whichever one its config asked for
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Board {
+    Square(SquareBoard),
+    Hex(HexBoard),
+}
+
+impl Board {
+    pub fn new(shape: Shape, width: u32, height: u32) -> Self {
+        match shape {
+            Shape::Square => Board::Square(SquareBoard::new(width, height)),
+            Shape::Hex => Board::Hex(HexBoard::new(width, height)),
+        }
+    }
+
+    pub fn get_shape(&self) -> Shape {
+        match self {
+            Board::Square(_) => Shape::Square,
+            Board::Hex(_) => Shape::Hex,
+        }
+    }
+
+    fn topology(&self) -> &dyn Topology {
+        match self {
+            Board::Square(board) => board,
+            Board::Hex(board) => board,
+        }
+    }
+}
+
+impl Topology for Board {
+    fn directions(&self) -> &'static [Direction] {
+        self.topology().directions()
+    }
+
+    fn neighbor(&self, at: &Coord, direction: Direction) -> Coord {
+        self.topology().neighbor(at, direction)
+    }
+
+    fn distance(&self, from: &Coord, to: &Coord, wrap: bool) -> u32 {
+        self.topology().distance(from, to, wrap)
+    }
+
+    fn get_xrange(&self) -> (i32, i32) {
+        self.topology().get_xrange()
+    }
+
+    fn get_yrange(&self) -> (i32, i32) {
+        self.topology().get_yrange()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, VecDeque};
+
+    // steps from one cell to every other, one neighbor at a time
+    fn walk(board: &Board, from: Coord, wrap: bool) -> HashMap<Coord, u32> {
+        let mut seen = HashMap::from([(from, 0)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(at) = queue.pop_front() {
+            for &direction in board.directions() {
+                let next = match board.neighbor(&at, direction) {
+                    next if board.in_bounds(&next) => next,
+                    next if wrap => board.wrap(&next),
+                    _ => continue,
+                };
+                if !seen.contains_key(&next) {
+                    seen.insert(next, seen[&at] + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        seen
+    }
+
+    #[test]
+    fn hex_neighbors_lean_with_the_row() {
+        let board = HexBoard::new(10, 8);
+
+        // grid row 1 is pushed over, row 2 isn't
+        let shifted = Coord::new(0, -3);
+        assert!(board.is_shifted(&shifted));
+        assert_eq!(board.neighbor(&shifted, Direction::UpRight), Coord::new(1, -4));
+        assert_eq!(board.neighbor(&shifted, Direction::UpLeft), Coord::new(0, -4));
+        assert_eq!(board.neighbor(&shifted, Direction::DownRight), Coord::new(1, -2));
+        assert_eq!(board.neighbor(&shifted, Direction::DownLeft), Coord::new(0, -2));
+
+        let unshifted = Coord::new(0, -2);
+        assert!(!board.is_shifted(&unshifted));
+        assert_eq!(board.neighbor(&unshifted, Direction::UpRight), Coord::new(0, -3));
+        assert_eq!(board.neighbor(&unshifted, Direction::UpLeft), Coord::new(-1, -3));
+        assert_eq!(board.neighbor(&unshifted, Direction::Right), Coord::new(1, -2));
+        assert_eq!(board.neighbor(&unshifted, Direction::Left), Coord::new(-1, -2));
+
+        // and going back the other way gets to where it started
+        for y in -4..4 {
+            for &direction in board.directions() {
+                let at = Coord::new(0, y);
+                let there = board.neighbor(&at, direction);
+                assert_eq!(board.neighbor(&there, direction.get_opposite()), at, "{:?} {:?}", at, direction);
+            }
+        }
+    }
+
+    #[test]
+    fn distance_across_the_edge() {
+        let square = SquareBoard::new(10, 8);
+        assert_eq!(square.distance(&Coord::new(-5, 0), &Coord::new(4, 0), false), 9);
+        assert_eq!(square.distance(&Coord::new(-5, 0), &Coord::new(4, 0), true), 1);
+        assert_eq!(square.distance(&Coord::new(-5, -4), &Coord::new(4, 3), true), 2);
+
+        let hex = HexBoard::new(10, 8);
+        assert_eq!(hex.distance(&Coord::new(-5, 0), &Coord::new(4, 0), false), 9);
+        assert_eq!(hex.distance(&Coord::new(-5, 0), &Coord::new(4, 0), true), 1);
+        assert_eq!(hex.distance(&Coord::new(0, -4), &Coord::new(0, 3), false), 7);
+        assert_eq!(hex.distance(&Coord::new(0, -4), &Coord::new(0, 3), true), 1);
+    }
+
+    #[test]
+    fn distance_is_the_shortest_walk() {
+        for shape in [Shape::Square, Shape::Hex] {
+            let board = Board::new(shape, 10, 8);
+            for wrap in [false, true] {
+                for from in [Coord::new(0, 0), Coord::new(-5, -4), Coord::new(4, 3), Coord::new(2, -3)] {
+                    let steps = walk(&board, from, wrap);
+                    assert_eq!(steps.len(), 80);
+                    for (to, steps) in steps {
+                        assert_eq!(board.distance(&from, &to, wrap), steps, "{} {:?} {:?} {}", shape, from, to, wrap);
+                    }
+                }
+            }
+        }
+    }
+}